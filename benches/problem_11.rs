@@ -0,0 +1,28 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rust_aoc::year_2015::problem_11::*;
+
+fn bench_next_valid(c: &mut Criterion) {
+    let policy = PasswordPolicy::default();
+    for password in &["abcdefgh", "ghijklmn", "hepxcrrq"] {
+        c.bench_function(&format!("skip-ahead {}", password), |b| {
+            b.iter(|| next_valid(black_box(password.as_bytes()), &policy))
+        });
+        c.bench_function(&format!("linear {}", password), |b| {
+            b.iter(|| next_valid_linear(black_box(password.as_bytes()), &policy))
+        });
+    }
+
+    let policy = PasswordPolicy::default()
+        .with_rule(PasswordRule::Straight(5))
+        .with_rule(PasswordRule::DistinctPairs(3));
+    c.bench_function("skip-ahead zzzzzzzy (strict policy)", |b| {
+        b.iter(|| next_valid(black_box(b"zzzzzzzy"), &policy))
+    });
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = bench_next_valid
+}
+criterion_main!(benches);
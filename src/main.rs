@@ -1,3 +1,31 @@
+fn get_option<'a>(args: &'a [String], name: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|arg| arg == name)
+        .and_then(|i| args.get(i + 1))
+        .map(String::as_str)
+}
+
+fn has_flag(args: &[String], name: &str) -> bool {
+    args.iter().any(|arg| arg == name)
+}
+
+fn solution<T: std::fmt::Display>(solution: Option<T>) -> String {
+    match solution {
+        Some(solution) => solution.to_string(),
+        None => {
+            eprintln!("No solution");
+            std::process::exit(1);
+        }
+    }
+}
+
+fn or_exit<T, E: std::fmt::Display>(result: Result<T, E>) -> T {
+    result.unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    })
+}
+
 fn main() {
     let args: Vec<String> = std::env::args().collect();
     let problem = args.get(1).expect("Problem is not specified");
@@ -9,34 +37,194 @@ fn main() {
     use rust_aoc::year_2020;
     let result = match &problem[..] {
         "2015_01_a" => year_2015::problem_01::solve_a(stdin).to_string(),
-        "2015_01_b" => year_2015::problem_01::solve_b(stdin).to_string(),
-        "2015_02_a" => year_2015::problem_02::solve_a(stdin).to_string(),
-        "2015_02_b" => year_2015::problem_02::solve_b(stdin).to_string(),
+        "2015_01_b" => solution(year_2015::problem_01::solve_b(stdin)),
+        "2015_01_trace" => {
+            let trace = or_exit(year_2015::problem_01::trace(
+                stdin,
+                has_flag(&args, "--strict"),
+            ));
+            match get_option(&args, "--floor") {
+                Some(floor) => {
+                    let floor = floor.parse().expect("Invalid floor");
+                    match trace.first_reached(floor) {
+                        Some(position) => {
+                            format!("{}\nFloor {} first reached at {}", trace, floor, position)
+                        }
+                        None => format!("{}\nFloor {} is never reached", trace, floor),
+                    }
+                }
+                None => trace.to_string(),
+            }
+        }
+        "2015_02_a" => or_exit(year_2015::problem_02::solve_a(stdin)).to_string(),
+        "2015_02_b" => or_exit(year_2015::problem_02::solve_b(stdin)).to_string(),
+        "2015_02_report" => match get_option(&args, "--format").unwrap_or("text") {
+            "text" => or_exit(year_2015::problem_02::report(stdin)),
+            "csv" => or_exit(year_2015::problem_02::report_csv(stdin)),
+            _ => panic!("Unknown format"),
+        },
         "2015_03_a" => year_2015::problem_03::solve_a(stdin).to_string(),
         "2015_03_b" => year_2015::problem_03::solve_b(stdin).to_string(),
         "2015_04_a" => year_2015::problem_04::solve_a(stdin).to_string(),
         "2015_04_b" => year_2015::problem_04::solve_b(stdin).to_string(),
+        "2015_04" => {
+            use year_2015::problem_04::{HashPrefix, Target};
+
+            let algorithm = get_option(&args, "--hash")
+                .unwrap_or("md5")
+                .parse()
+                .unwrap_or_else(|e| panic!("{}", e));
+            let prefix = match (get_option(&args, "--zeroes"), get_option(&args, "--prefix")) {
+                (Some(zeroes), None) => {
+                    HashPrefix::zeroes(zeroes.parse().expect("Invalid leading zeroes"))
+                }
+                (None, Some(prefix)) => prefix.parse().unwrap_or_else(|e| panic!("{}", e)),
+                _ => panic!("Either leading zeroes or a prefix must be specified"),
+            };
+            let threads = get_option(&args, "--threads")
+                .map(|threads| threads.parse().expect("Invalid threads count"))
+                .unwrap_or_else(year_2015::problem_04::default_threads);
+            let target = Target::new(algorithm, prefix).unwrap_or_else(|e| panic!("{}", e));
+            year_2015::problem_04::mine_input(stdin, &target, threads).to_string()
+        }
+        "2015_05_a" if has_flag(&args, "--explain") => year_2015::problem_05::explain_a(stdin),
+        "2015_05_b" if has_flag(&args, "--explain") => year_2015::problem_05::explain_b(stdin),
         "2015_05_a" => year_2015::problem_05::solve_a(stdin).to_string(),
         "2015_05_b" => year_2015::problem_05::solve_b(stdin).to_string(),
+        "2015_05" => {
+            let path = get_option(&args, "--rules").expect("Rules file is not specified");
+            let rules = std::fs::read_to_string(path).expect("Failed to read the rules file");
+            let rules = year_2015::problem_05::RuleSet::parse(&rules);
+            if has_flag(&args, "--explain") {
+                year_2015::problem_05::explain_with_rules(stdin, &rules)
+            } else {
+                year_2015::problem_05::solve_with_rules(stdin, &rules).to_string()
+            }
+        }
         "2015_06_a" => year_2015::problem_06::solve_a(stdin).to_string(),
         "2015_06_b" => year_2015::problem_06::solve_b(stdin).to_string(),
-        "2015_07_a" => year_2015::problem_07::solve_a(stdin).to_string(),
-        "2015_07_b" => year_2015::problem_07::solve_b(stdin).to_string(),
+        "2015_06" => {
+            let rules = get_option(&args, "--rules").expect("Rules are not specified");
+            year_2015::problem_06::solve_with_rules(stdin, rules).to_string()
+        }
+        "2015_06_render" => {
+            let rules = get_option(&args, "--rules").expect("Rules are not specified");
+            match get_option(&args, "--format").unwrap_or("ascii") {
+                "pgm" => year_2015::problem_06::render_pgm(stdin, rules),
+                "ascii" => {
+                    let scale = get_option(&args, "--scale")
+                        .map_or(std::num::NonZeroUsize::new(20).unwrap(), |scale| {
+                            scale.parse().expect("Scale must be a positive number")
+                        });
+                    year_2015::problem_06::render_ascii(stdin, rules, scale)
+                }
+                _ => panic!("Unknown format"),
+            }
+        }
+        "2015_06_replay" => {
+            let rules = get_option(&args, "--rules").expect("Rules are not specified");
+            let snapshot_every = get_option(&args, "--snapshot-every").map(|every| {
+                every
+                    .parse()
+                    .expect("Snapshot interval must be a positive number")
+            });
+            let scale = get_option(&args, "--scale")
+                .map_or(std::num::NonZeroUsize::new(20).unwrap(), |scale| {
+                    scale.parse().expect("Scale must be a positive number")
+                });
+
+            year_2015::problem_06::replay(stdin, rules, snapshot_every)
+                .map(|step| {
+                    let totals = format!("{} {} {}", step.step, step.lit, step.brightness);
+                    match step.snapshot {
+                        Some(grid) => format!(
+                            "{}\n{}",
+                            totals,
+                            year_2015::problem_06::render_grid_ascii(&grid, scale)
+                        ),
+                        None => totals,
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        }
+        "2015_07_a" => solution(year_2015::problem_07::solve_a(stdin)),
+        "2015_07_b" => solution(year_2015::problem_07::solve_b(stdin)),
+        "2015_07_simplify" => {
+            let output = get_option(&args, "--output").expect("Output wire is not specified");
+            year_2015::problem_07::simplify_netlist(stdin, output)
+        }
+        "2015_08_a" if get_option(&args, "--dialect").is_some() => {
+            let dialect = get_option(&args, "--dialect").unwrap();
+            let scan = has_flag(&args, "--scan");
+            year_2015::problem_08::solve_a_with_dialect(stdin, dialect, scan).to_string()
+        }
+        "2015_08_b" if get_option(&args, "--dialect").is_some() => {
+            let dialect = get_option(&args, "--dialect").unwrap();
+            let scan = has_flag(&args, "--scan");
+            year_2015::problem_08::solve_b_with_dialect(stdin, dialect, scan).to_string()
+        }
         "2015_08_a" => year_2015::problem_08::solve_a(stdin).to_string(),
         "2015_08_b" => year_2015::problem_08::solve_b(stdin).to_string(),
-        "2015_09_a" => year_2015::problem_09::solve_a(stdin).to_string(),
-        "2015_09_b" => year_2015::problem_09::solve_b(stdin).to_string(),
+        "2015_09_a" if has_flag(&args, "--explain") => {
+            or_exit(year_2015::problem_09::explain_a(stdin))
+        }
+        "2015_09_b" if has_flag(&args, "--explain") => {
+            or_exit(year_2015::problem_09::explain_b(stdin))
+        }
+        "2015_09_a" => solution(year_2015::problem_09::solve_a(stdin)),
+        "2015_09_b" => solution(year_2015::problem_09::solve_b(stdin)),
         "2015_10_a" => year_2015::problem_10::solve_a(stdin).to_string(),
         "2015_10_b" => year_2015::problem_10::solve_b(stdin).to_string(),
-        "2015_11_a" => year_2015::problem_11::solve_a(stdin).to_string(),
-        "2015_11_b" => year_2015::problem_11::solve_b(stdin).to_string(),
+        "2015_10" => {
+            let iterations = get_option(&args, "--iterations")
+                .expect("Iterations are not specified")
+                .parse()
+                .expect("Invalid iterations count");
+            if let Some(base) = get_option(&args, "--base") {
+                let base = base.parse().expect("Invalid base");
+                let transform = year_2015::problem_10::RunLengthTransform::new(base);
+                year_2015::problem_10::solve_with_transform(stdin, transform, iterations)
+                    .to_string()
+            } else if has_flag(&args, "--direct") {
+                year_2015::problem_10::solve_direct(stdin, iterations).to_string()
+            } else {
+                year_2015::problem_10::solve_conway(stdin, iterations).to_string()
+            }
+        }
+        "2015_11_a" => solution(year_2015::problem_11::solve_a(stdin)),
+        "2015_11_b" => solution(year_2015::problem_11::solve_b(stdin)),
+        "2015_11" => {
+            let count = get_option(&args, "--count")
+                .expect("Count is not specified")
+                .parse()
+                .expect("Invalid count");
+            let linear = has_flag(&args, "--linear");
+            year_2015::problem_11::next_passwords(stdin, count, linear).join("\n")
+        }
         "2015_12_a" => year_2015::problem_12::solve_a(stdin).to_string(),
         "2015_12_b" => year_2015::problem_12::solve_b(stdin).to_string(),
-        "2015_13_a" => year_2015::problem_13::solve_a(stdin).to_string(),
-        "2015_13_b" => year_2015::problem_13::solve_b(stdin).to_string(),
+        "2015_12" => {
+            let rules = get_option(&args, "--rules").expect("Rules are not specified");
+            let rules = year_2015::problem_12::Rules::parse(rules);
+            match get_option(&args, "--subtotals") {
+                Some(depth) => {
+                    let depth = depth.parse().expect("Invalid subtotals depth");
+                    let (total, subtotals) = year_2015::problem_12::subtotals(stdin, &rules, depth);
+                    subtotals
+                        .iter()
+                        .map(|subtotal| format!("{}\n", subtotal))
+                        .chain(std::iter::once(format!("Total = {}", total)))
+                        .collect()
+                }
+                None => year_2015::problem_12::solve_with_rules(stdin, &rules).to_string(),
+            }
+        }
+        "2015_13_a" => solution(year_2015::problem_13::solve_a(stdin)),
+        "2015_13_b" => solution(year_2015::problem_13::solve_b(stdin)),
 
-        "2020_01_a" => year_2020::problem_01::solve_a(stdin).to_string(),
-        "2020_01_b" => year_2020::problem_01::solve_b(stdin).to_string(),
+        "2020_01_a" => solution(year_2020::problem_01::solve_a(stdin)),
+        "2020_01_b" => solution(year_2020::problem_01::solve_b(stdin)),
         _ => panic!("Unknown problem"),
     };
 
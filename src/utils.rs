@@ -1 +1,3 @@
+pub mod decimal;
+pub mod graph;
 pub mod parsing;
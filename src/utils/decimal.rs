@@ -0,0 +1,361 @@
+use std::convert::TryFrom;
+
+const LIMB_BASE: u64 = 1_000_000_000;
+const LIMB_DIGITS: usize = 9;
+const MAX_EXPONENT: i64 = 1000;
+
+type Limbs = Vec<u32>;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Decimal {
+    negative: bool,
+    limbs: Limbs,
+    scale: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseDecimalError(String);
+
+impl std::fmt::Display for ParseDecimalError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Invalid number: {}", self.0)
+    }
+}
+
+fn trim_limbs(mut limbs: Limbs) -> Limbs {
+    while limbs.last() == Some(&0) {
+        limbs.pop();
+    }
+    limbs
+}
+
+fn mul_add_small(limbs: &mut Limbs, mul: u32, add: u32) {
+    let mut carry = add as u64;
+    for limb in limbs.iter_mut() {
+        let value = *limb as u64 * mul as u64 + carry;
+        *limb = (value % LIMB_BASE) as u32;
+        carry = value / LIMB_BASE;
+    }
+    while carry != 0 {
+        limbs.push((carry % LIMB_BASE) as u32);
+        carry /= LIMB_BASE;
+    }
+}
+
+fn div_rem_small(limbs: &[u32], div: u32) -> (Limbs, u32) {
+    let mut quotient = vec![0; limbs.len()];
+    let mut rem: u64 = 0;
+    for (i, &limb) in limbs.iter().enumerate().rev() {
+        let value = rem * LIMB_BASE + limb as u64;
+        quotient[i] = (value / div as u64) as u32;
+        rem = value % div as u64;
+    }
+
+    (trim_limbs(quotient), rem as u32)
+}
+
+fn cmp_limbs(a: &[u32], b: &[u32]) -> std::cmp::Ordering {
+    a.len()
+        .cmp(&b.len())
+        .then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add_limbs(a: &[u32], b: &[u32]) -> Limbs {
+    let mut result = Vec::with_capacity(a.len().max(b.len()) + 1);
+    let mut carry = 0;
+    for i in 0..a.len().max(b.len()) {
+        let value = *a.get(i).unwrap_or(&0) as u64 + *b.get(i).unwrap_or(&0) as u64 + carry;
+        result.push((value % LIMB_BASE) as u32);
+        carry = value / LIMB_BASE;
+    }
+    if carry != 0 {
+        result.push(carry as u32);
+    }
+
+    result
+}
+
+fn sub_limbs(a: &[u32], b: &[u32]) -> Limbs {
+    let mut result = Vec::with_capacity(a.len());
+    let mut borrow = 0;
+    for (i, &limb) in a.iter().enumerate() {
+        let subtrahend = *b.get(i).unwrap_or(&0) as i64 + borrow;
+        let mut value = limb as i64 - subtrahend;
+        borrow = 0;
+        if value < 0 {
+            value += LIMB_BASE as i64;
+            borrow = 1;
+        }
+        result.push(value as u32);
+    }
+
+    trim_limbs(result)
+}
+
+impl Decimal {
+    pub fn zero() -> Self {
+        Self::default()
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    fn rescaled(&self, scale: u32) -> Limbs {
+        let mut limbs = self.limbs.clone();
+        for _ in self.scale..scale {
+            mul_add_small(&mut limbs, 10, 0);
+        }
+        limbs
+    }
+
+    fn normalized(mut self) -> Self {
+        while self.scale > 0 {
+            let (quotient, rem) = div_rem_small(&self.limbs, 10);
+            if rem != 0 {
+                break;
+            }
+            self.limbs = quotient;
+            self.scale -= 1;
+        }
+
+        if self.is_zero() {
+            self.negative = false;
+            self.scale = 0;
+        }
+
+        self
+    }
+}
+
+impl std::str::FromStr for Decimal {
+    type Err = ParseDecimalError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let error = || ParseDecimalError(input.to_string());
+
+        let (negative, unsigned) = match input.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, input),
+        };
+
+        let (mantissa, exponent) = match unsigned.find(['e', 'E']) {
+            Some(pos) => {
+                let exponent = unsigned[pos + 1..].parse::<i64>().map_err(|_| error())?;
+                (&unsigned[..pos], exponent)
+            }
+            None => (unsigned, 0),
+        };
+
+        let (integer, fraction) = match mantissa.find('.') {
+            Some(pos) => (&mantissa[..pos], &mantissa[pos + 1..]),
+            None => (mantissa, ""),
+        };
+
+        let digits = integer.bytes().chain(fraction.bytes());
+        if integer.is_empty() || (mantissa.contains('.') && fraction.is_empty()) {
+            return Err(error());
+        }
+
+        let mut limbs = Limbs::new();
+        for digit in digits {
+            if !digit.is_ascii_digit() {
+                return Err(error());
+            }
+            mul_add_small(&mut limbs, 10, (digit - b'0') as u32);
+        }
+
+        let exponent = if limbs.iter().all(|&limb| limb == 0) {
+            0
+        } else if exponent.abs() > MAX_EXPONENT {
+            return Err(error());
+        } else {
+            exponent
+        };
+
+        let mut scale = fraction.len() as i64 - exponent;
+        while scale < 0 {
+            mul_add_small(&mut limbs, 10, 0);
+            scale += 1;
+        }
+
+        let decimal = Decimal {
+            negative,
+            limbs: trim_limbs(limbs),
+            scale: u32::try_from(scale).map_err(|_| error())?,
+        };
+
+        Ok(decimal.normalized())
+    }
+}
+
+impl From<i64> for Decimal {
+    fn from(n: i64) -> Self {
+        let mut limbs = Limbs::new();
+        let mut magnitude = n.unsigned_abs();
+        while magnitude != 0 {
+            limbs.push((magnitude % LIMB_BASE) as u32);
+            magnitude /= LIMB_BASE;
+        }
+
+        Decimal {
+            negative: n < 0,
+            limbs,
+            scale: 0,
+        }
+    }
+}
+
+impl std::ops::Add for Decimal {
+    type Output = Decimal;
+
+    fn add(self, other: Decimal) -> Decimal {
+        let scale = self.scale.max(other.scale);
+        let a = self.rescaled(scale);
+        let b = other.rescaled(scale);
+
+        let (negative, limbs) = if self.negative == other.negative {
+            (self.negative, add_limbs(&a, &b))
+        } else if cmp_limbs(&a, &b) == std::cmp::Ordering::Less {
+            (other.negative, sub_limbs(&b, &a))
+        } else {
+            (self.negative, sub_limbs(&a, &b))
+        };
+
+        Decimal {
+            negative,
+            limbs,
+            scale,
+        }
+        .normalized()
+    }
+}
+
+impl std::ops::AddAssign for Decimal {
+    fn add_assign(&mut self, other: Decimal) {
+        *self = std::mem::take(self) + other;
+    }
+}
+
+impl std::ops::Mul<u32> for Decimal {
+    type Output = Decimal;
+
+    fn mul(mut self, factor: u32) -> Decimal {
+        mul_add_small(&mut self.limbs, factor, 0);
+        self.limbs = trim_limbs(std::mem::take(&mut self.limbs));
+        self.normalized()
+    }
+}
+
+impl std::iter::Sum for Decimal {
+    fn sum<I: Iterator<Item = Decimal>>(iter: I) -> Decimal {
+        iter.fold(Decimal::zero(), |acc, n| acc + n)
+    }
+}
+
+impl std::fmt::Display for Decimal {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let mut digits = match self.limbs.split_last() {
+            None => String::from("0"),
+            Some((most_significant, rest)) => {
+                let mut digits = most_significant.to_string();
+                for limb in rest.iter().rev() {
+                    digits.push_str(&format!("{:0width$}", limb, width = LIMB_DIGITS));
+                }
+                digits
+            }
+        };
+
+        let scale = self.scale as usize;
+        if scale > 0 {
+            if digits.len() <= scale {
+                digits = "0".repeat(scale - digits.len() + 1) + &digits;
+            }
+            digits.insert(digits.len() - scale, '.');
+        }
+
+        if self.negative {
+            write!(f, "-")?;
+        }
+        write!(f, "{}", digits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str) -> Decimal {
+        input.parse().unwrap()
+    }
+
+    #[test]
+    fn check_parsing() {
+        assert_eq!("0", parse("0").to_string());
+        assert_eq!("0", parse("-0.000").to_string());
+        assert_eq!("42", parse("42").to_string());
+        assert_eq!("-1.5", parse("-1.50").to_string());
+        assert_eq!("1500", parse("1.5e3").to_string());
+        assert_eq!("0.015", parse("1.5E-2").to_string());
+        assert_eq!("0.000000000001", parse("1e-12").to_string());
+        assert_eq!(
+            "123456789012345678901234567890",
+            parse("123456789012345678901234567890").to_string()
+        );
+        assert_eq!(Decimal::from(-1234567890123), parse("-1234567890123"));
+
+        assert!("".parse::<Decimal>().is_err());
+        assert!("-".parse::<Decimal>().is_err());
+        assert!("1.".parse::<Decimal>().is_err());
+        assert!("1e".parse::<Decimal>().is_err());
+        assert!("1x".parse::<Decimal>().is_err());
+
+        assert_eq!(1001, parse("1e1000").to_string().len());
+        assert_eq!("0", parse("0e-2000000000").to_string());
+        assert!("1e1001".parse::<Decimal>().is_err());
+        assert!("1e-2000000000".parse::<Decimal>().is_err());
+        assert!("-2.5E+99999999999".parse::<Decimal>().is_err());
+    }
+
+    #[test]
+    fn check_arithmetic() {
+        assert_eq!(parse("3"), parse("1") + parse("2"));
+        assert_eq!(parse("0.3"), parse("0.1") + parse("0.2"));
+        assert_eq!(parse("-0.5"), parse("1") + parse("-1.5"));
+        assert_eq!(Decimal::zero(), parse("1.25") + parse("-1.25"));
+        assert_eq!(
+            parse("1000000000000000000000000000000"),
+            parse("999999999999999999999999999999") + parse("1")
+        );
+        assert_eq!(
+            parse("-999999999999999999999999999999"),
+            parse("-1000000000000000000000000000000") + parse("1")
+        );
+        assert_eq!(
+            parse("9223372036854775808"),
+            Decimal::from(i64::MAX) + Decimal::from(1)
+        );
+
+        assert_eq!(parse("-3.75"), parse("-1.25") * 3);
+        assert_eq!(Decimal::zero(), Decimal::zero() * 7);
+        assert_eq!(
+            parse("4294967295000000000000000000"),
+            parse("1000000000000000000") * u32::MAX
+        );
+        assert_eq!(parse("4294967290705032705"), parse("999999999") * u32::MAX);
+        assert_eq!(
+            parse("4294967290705032706"),
+            parse("999999999") * u32::MAX + parse("1")
+        );
+        assert!(parse("999999999") * u32::MAX != parse("4294967290705032704"));
+        assert_eq!(
+            parse("-4294967294999999995705032705"),
+            parse("-999999999999999999") * u32::MAX
+        );
+
+        let sum: Decimal = vec![parse("0.5"), parse("1e2"), parse("-0.25")]
+            .into_iter()
+            .sum();
+        assert_eq!("100.25", sum.to_string());
+    }
+}
@@ -0,0 +1,484 @@
+pub type NodeId = usize;
+
+pub trait Weight: Copy + Ord + Default + std::ops::Add<Output = Self> {}
+impl<T: Copy + Ord + Default + std::ops::Add<Output = T>> Weight for T {}
+
+pub type IsBetter<W> = fn(W, W) -> bool;
+
+#[derive(Debug, Default)]
+pub struct NodeInterner {
+    names: Vec<String>,
+    ids: std::collections::HashMap<String, NodeId>,
+}
+
+impl NodeInterner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn intern(&mut self, name: &str) -> NodeId {
+        if let Some(&id) = self.ids.get(name) {
+            return id;
+        }
+
+        let id = self.names.len();
+        self.names.push(name.to_string());
+        self.ids.insert(name.to_string(), id);
+        id
+    }
+
+    pub fn get(&self, name: &str) -> Option<NodeId> {
+        self.ids.get(name).copied()
+    }
+
+    pub fn name(&self, id: NodeId) -> &str {
+        &self.names[id]
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
+
+pub trait WeightedGraph<W: Weight> {
+    fn node_count(&self) -> usize;
+    fn weight(&self, from: NodeId, to: NodeId) -> Option<W>;
+    fn edges(&self, from: NodeId) -> Vec<(NodeId, W)>;
+}
+
+#[derive(Debug, Clone)]
+pub struct DenseGraph<W> {
+    size: usize,
+    weights: Vec<Option<W>>,
+}
+
+impl<W: Weight> DenseGraph<W> {
+    pub fn new(size: usize) -> Self {
+        DenseGraph {
+            size,
+            weights: vec![None; size * size],
+        }
+    }
+
+    pub fn add_edge(&mut self, from: NodeId, to: NodeId, weight: W) {
+        self.weights[from * self.size + to] = Some(weight);
+    }
+
+    pub fn add_undirected_edge(&mut self, a: NodeId, b: NodeId, weight: W) {
+        self.add_edge(a, b, weight);
+        self.add_edge(b, a, weight);
+    }
+
+    pub fn remove_edge(&mut self, from: NodeId, to: NodeId) {
+        self.weights[from * self.size + to] = None;
+    }
+}
+
+impl<W: Weight> WeightedGraph<W> for DenseGraph<W> {
+    fn node_count(&self) -> usize {
+        self.size
+    }
+
+    fn weight(&self, from: NodeId, to: NodeId) -> Option<W> {
+        self.weights[from * self.size + to]
+    }
+
+    fn edges(&self, from: NodeId) -> Vec<(NodeId, W)> {
+        (0..self.size)
+            .filter_map(|to| self.weight(from, to).map(|weight| (to, weight)))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SparseGraph<W> {
+    edges: Vec<Vec<(NodeId, W)>>,
+}
+
+impl<W: Weight> SparseGraph<W> {
+    pub fn new(size: usize) -> Self {
+        SparseGraph {
+            edges: vec![Vec::new(); size],
+        }
+    }
+
+    pub fn add_edge(&mut self, from: NodeId, to: NodeId, weight: W) {
+        let edges = &mut self.edges[from];
+        match edges.iter_mut().find(|(node, _)| *node == to) {
+            Some(edge) => edge.1 = weight,
+            None => edges.push((to, weight)),
+        }
+    }
+
+    pub fn add_undirected_edge(&mut self, a: NodeId, b: NodeId, weight: W) {
+        self.add_edge(a, b, weight);
+        self.add_edge(b, a, weight);
+    }
+
+    pub fn remove_edge(&mut self, from: NodeId, to: NodeId) {
+        self.edges[from].retain(|&(node, _)| node != to);
+    }
+}
+
+impl<W: Weight> WeightedGraph<W> for SparseGraph<W> {
+    fn node_count(&self) -> usize {
+        self.edges.len()
+    }
+
+    fn weight(&self, from: NodeId, to: NodeId) -> Option<W> {
+        self.edges[from]
+            .iter()
+            .find(|(node, _)| *node == to)
+            .map(|&(_, weight)| weight)
+    }
+
+    fn edges(&self, from: NodeId) -> Vec<(NodeId, W)> {
+        self.edges[from].clone()
+    }
+}
+
+pub fn bfs<W: Weight>(graph: &impl WeightedGraph<W>, start: NodeId) -> Vec<Option<usize>> {
+    let mut hops = vec![None; graph.node_count()];
+    let mut queue = std::collections::VecDeque::new();
+
+    hops[start] = Some(0);
+    queue.push_back(start);
+
+    while let Some(node) = queue.pop_front() {
+        let node_hops = hops[node].unwrap();
+        for (next, _) in graph.edges(node) {
+            if hops[next].is_none() {
+                hops[next] = Some(node_hops + 1);
+                queue.push_back(next);
+            }
+        }
+    }
+
+    hops
+}
+
+pub fn dijkstra<W: Weight>(graph: &impl WeightedGraph<W>, start: NodeId) -> Vec<Option<W>> {
+    use std::cmp::Reverse;
+
+    let mut distances = vec![None; graph.node_count()];
+    let mut queue = std::collections::BinaryHeap::new();
+    queue.push(Reverse((W::default(), start)));
+
+    while let Some(Reverse((distance, node))) = queue.pop() {
+        if distances[node].is_some() {
+            continue;
+        }
+        distances[node] = Some(distance);
+
+        for (next, weight) in graph.edges(node) {
+            if distances[next].is_none() {
+                queue.push(Reverse((distance + weight, next)));
+            }
+        }
+    }
+
+    distances
+}
+
+pub fn floyd_warshall<W: Weight>(graph: &impl WeightedGraph<W>) -> Vec<Vec<Option<W>>> {
+    let n = graph.node_count();
+    let mut distances: Vec<Vec<Option<W>>> = (0..n)
+        .map(|from| (0..n).map(|to| graph.weight(from, to)).collect())
+        .collect();
+
+    for (node, row) in distances.iter_mut().enumerate() {
+        row[node] = Some(W::default());
+    }
+
+    for via in 0..n {
+        for from in 0..n {
+            for to in 0..n {
+                let through_via = distances[from][via]
+                    .zip(distances[via][to])
+                    .map(|(a, b)| a + b);
+
+                if let Some(candidate) = through_via {
+                    let is_shorter = match distances[from][to] {
+                        Some(current) => candidate < current,
+                        None => true,
+                    };
+                    if is_shorter {
+                        distances[from][to] = Some(candidate);
+                    }
+                }
+            }
+        }
+    }
+
+    distances
+}
+
+struct SubsetTable<W> {
+    n: usize,
+    best: Vec<W>,
+    reached: Vec<bool>,
+}
+
+impl<W: Weight> SubsetTable<W> {
+    fn new(n: usize) -> Self {
+        SubsetTable {
+            n,
+            best: vec![W::default(); (1 << n) * n],
+            reached: vec![false; (1 << n) * n],
+        }
+    }
+
+    fn get(&self, visited: usize, last: NodeId) -> Option<W> {
+        let index = visited * self.n + last;
+        if self.reached[index] {
+            Some(self.best[index])
+        } else {
+            None
+        }
+    }
+
+    fn update(&mut self, visited: usize, last: NodeId, candidate: W, is_better: IsBetter<W>) {
+        let index = visited * self.n + last;
+        if !self.reached[index] || is_better(candidate, self.best[index]) {
+            self.best[index] = candidate;
+            self.reached[index] = true;
+        }
+    }
+}
+
+fn fill_subset_table<W: Weight>(
+    graph: &impl WeightedGraph<W>,
+    starts: impl Iterator<Item = (NodeId, W)>,
+    is_better: IsBetter<W>,
+) -> SubsetTable<W> {
+    let n = graph.node_count();
+    let mut table = SubsetTable::new(n);
+    for (start, weight) in starts {
+        table.update(1 << start, start, weight, is_better);
+    }
+
+    for visited in 1..(1 << n) {
+        for last in 0..n {
+            if let Some(current) = table.get(visited, last) {
+                for next in (0..n).filter(|next| visited & (1 << next) == 0) {
+                    if let Some(weight) = graph.weight(last, next) {
+                        table.update(visited | (1 << next), next, current + weight, is_better);
+                    }
+                }
+            }
+        }
+    }
+
+    table
+}
+
+fn restore_path<W: Weight>(
+    graph: &impl WeightedGraph<W>,
+    table: &SubsetTable<W>,
+    mut visited: usize,
+    last: NodeId,
+) -> Vec<NodeId> {
+    let mut path = vec![last];
+    while visited.count_ones() > 1 {
+        let last = *path.last().unwrap();
+        let distance = table.get(visited, last);
+        visited ^= 1 << last;
+
+        let prev = (0..graph.node_count())
+            .find(|&prev| {
+                let prev_distance = table.get(visited, prev);
+                prev_distance
+                    .zip(graph.weight(prev, last))
+                    .map(|(a, b)| a + b)
+                    == distance
+            })
+            .unwrap();
+        path.push(prev);
+    }
+    path.reverse();
+
+    path
+}
+
+fn pick_best<W: Weight>(
+    candidates: impl Iterator<Item = (W, NodeId)>,
+    is_better: IsBetter<W>,
+) -> Option<(W, NodeId)> {
+    candidates.reduce(|a, b| if is_better(b.0, a.0) { b } else { a })
+}
+
+/// Finds the best path visiting every node once (Held-Karp). Takes O(2^n * n^2) time,
+/// and the subset table holds `(1 << n) * n` entries: roughly 190 MB at n = 20.
+pub fn best_hamiltonian_path<W: Weight>(
+    graph: &impl WeightedGraph<W>,
+    is_better: IsBetter<W>,
+) -> Option<(W, Vec<NodeId>)> {
+    let n = graph.node_count();
+    let all_visited = (1 << n) - 1;
+    let starts = (0..n).map(|start| (start, W::default()));
+    let table = fill_subset_table(graph, starts, is_better);
+
+    let candidates = (0..n).filter_map(|last| table.get(all_visited, last).map(|w| (w, last)));
+    let (total, last) = pick_best(candidates, is_better)?;
+
+    Some((total, restore_path(graph, &table, all_visited, last)))
+}
+
+struct WithoutFirstNode<'a, G>(&'a G);
+
+impl<'a, W: Weight, G: WeightedGraph<W>> WeightedGraph<W> for WithoutFirstNode<'a, G> {
+    fn node_count(&self) -> usize {
+        self.0.node_count() - 1
+    }
+
+    fn weight(&self, from: NodeId, to: NodeId) -> Option<W> {
+        self.0.weight(from + 1, to + 1)
+    }
+
+    fn edges(&self, from: NodeId) -> Vec<(NodeId, W)> {
+        self.0
+            .edges(from + 1)
+            .into_iter()
+            .filter(|&(to, _)| to != 0)
+            .map(|(to, weight)| (to - 1, weight))
+            .collect()
+    }
+}
+
+/// Finds the best cycle through every node, fixing node 0 as the start. The subset
+/// table covers the other n - 1 nodes, so 21 nodes cost as much memory as a 20-node path.
+pub fn best_hamiltonian_cycle<W: Weight>(
+    graph: &impl WeightedGraph<W>,
+    is_better: IsBetter<W>,
+) -> Option<(W, Vec<NodeId>)> {
+    let n = graph.node_count();
+    if n <= 1 {
+        return if n == 1 {
+            Some((W::default(), vec![0]))
+        } else {
+            None
+        };
+    }
+
+    // the cycle always starts at node 0, so the subset table only has to cover the rest
+    let rest = WithoutFirstNode(graph);
+    let all_visited = (1 << (n - 1)) - 1;
+
+    let starts = (0..n - 1).filter_map(|node| graph.weight(0, node + 1).map(|w| (node, w)));
+    let table = fill_subset_table(&rest, starts, is_better);
+
+    let candidates = (0..n - 1).filter_map(|last| {
+        table
+            .get(all_visited, last)
+            .zip(graph.weight(last + 1, 0))
+            .map(|(w, closing)| (w + closing, last))
+    });
+    let (total, last) = pick_best(candidates, is_better)?;
+
+    let path = restore_path(&rest, &table, all_visited, last);
+    let cycle = std::iter::once(0)
+        .chain(path.into_iter().map(|node| node + 1))
+        .collect();
+
+    Some((total, cycle))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_line_graph(n: usize) -> SparseGraph<usize> {
+        let mut graph = SparseGraph::new(n);
+        for node in 1..n {
+            graph.add_undirected_edge(node - 1, node, node);
+        }
+        graph
+    }
+
+    #[test]
+    fn check_interner() {
+        let mut interner = NodeInterner::new();
+        assert!(interner.is_empty());
+        assert_eq!(0, interner.intern("London"));
+        assert_eq!(1, interner.intern("Dublin"));
+        assert_eq!(0, interner.intern("London"));
+        assert_eq!(2, interner.len());
+        assert_eq!("Dublin", interner.name(1));
+        assert_eq!(Some(1), interner.get("Dublin"));
+        assert_eq!(None, interner.get("Belfast"));
+    }
+
+    #[test]
+    fn check_graphs() {
+        let mut dense: DenseGraph<usize> = DenseGraph::new(3);
+        let mut sparse = SparseGraph::new(3);
+        dense.add_edge(0, 1, 5);
+        sparse.add_edge(0, 1, 5);
+        dense.add_undirected_edge(1, 2, 7);
+        sparse.add_undirected_edge(1, 2, 7);
+        sparse.add_edge(0, 1, 6);
+        dense.add_edge(0, 1, 6);
+        sparse.add_edge(2, 0, 1);
+        dense.add_edge(2, 0, 1);
+        sparse.remove_edge(2, 0);
+        dense.remove_edge(2, 0);
+
+        for graph in [&dense as &dyn WeightedGraph<usize>, &sparse].iter() {
+            assert_eq!(3, graph.node_count());
+            assert_eq!(Some(6), graph.weight(0, 1));
+            assert_eq!(None, graph.weight(1, 0));
+            assert_eq!(Some(7), graph.weight(2, 1));
+            assert_eq!(vec![(1, 7)], graph.edges(2));
+        }
+    }
+
+    #[test]
+    fn check_shortest_paths() {
+        let mut graph = make_line_graph(4);
+        graph.add_edge(0, 3, 100);
+
+        assert_eq!(vec![Some(0), Some(1), Some(2), Some(1)], bfs(&graph, 0));
+        assert_eq!(
+            vec![Some(0), Some(1), Some(3), Some(6)],
+            dijkstra(&graph, 0)
+        );
+
+        let distances = floyd_warshall(&graph);
+        assert_eq!(Some(6), distances[0][3]);
+        assert_eq!(Some(6), distances[3][0]);
+        assert_eq!(Some(0), distances[2][2]);
+
+        let disconnected: DenseGraph<usize> = DenseGraph::new(2);
+        assert_eq!(vec![Some(0), None], bfs(&disconnected, 0));
+        assert_eq!(vec![None, Some(0)], dijkstra(&disconnected, 1));
+        assert_eq!(None, floyd_warshall(&disconnected)[0][1]);
+    }
+
+    #[test]
+    fn check_hamiltonian() {
+        let graph = make_line_graph(5);
+        let (distance, path) = best_hamiltonian_path(&graph, |a, b| a < b).unwrap();
+        assert_eq!(10, distance);
+        assert!(path == vec![0, 1, 2, 3, 4] || path == vec![4, 3, 2, 1, 0]);
+        assert_eq!(None, best_hamiltonian_cycle(&graph, |a, b| a < b));
+
+        let mut graph = graph;
+        graph.add_undirected_edge(0, 4, 1);
+        let (distance, cycle) = best_hamiltonian_cycle(&graph, |a, b| a > b).unwrap();
+        assert_eq!(11, distance);
+        assert_eq!(0, cycle[0]);
+        assert_eq!(5, cycle.len());
+
+        let (distance, path) = best_hamiltonian_path(&graph, |a, b| a > b).unwrap();
+        assert_eq!(10, distance);
+        assert_eq!(5, path.len());
+
+        let empty: DenseGraph<usize> = DenseGraph::new(0);
+        assert_eq!(None, best_hamiltonian_path(&empty, |a, b| a < b));
+        assert_eq!(None, best_hamiltonian_cycle(&empty, |a, b| a < b));
+    }
+}
@@ -6,23 +6,118 @@ fn direction_to_step(c: u8) -> isize {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TraceError {
+    UnexpectedCharacter { position: usize, c: u8 },
+}
+
+impl std::fmt::Display for TraceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            TraceError::UnexpectedCharacter { position, c } => write!(
+                f,
+                "Unexpected character '{}' at position {}",
+                c.escape_ascii(),
+                position
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FloorTrace {
+    history: Vec<isize>,
+}
+
+impl FloorTrace {
+    pub fn from_bytes(directions: &[u8], strict: bool) -> Result<FloorTrace, TraceError> {
+        let directions = if strict {
+            directions.trim_ascii_end()
+        } else {
+            directions
+        };
+
+        let mut history = Vec::with_capacity(directions.len() + 1);
+        history.push(0);
+        for (i, &c) in directions.iter().enumerate() {
+            if strict && c != b'(' && c != b')' {
+                return Err(TraceError::UnexpectedCharacter { position: i + 1, c });
+            }
+            history.push(history[i] + direction_to_step(c));
+        }
+
+        Ok(FloorTrace { history })
+    }
+
+    pub fn history(&self) -> &[isize] {
+        &self.history
+    }
+
+    pub fn final_floor(&self) -> isize {
+        *self.history.last().unwrap()
+    }
+
+    pub fn min_floor(&self) -> isize {
+        *self.history.iter().min().unwrap()
+    }
+
+    pub fn max_floor(&self) -> isize {
+        *self.history.iter().max().unwrap()
+    }
+
+    pub fn first_reached(&self, floor: isize) -> Option<usize> {
+        self.history.iter().position(|&f| f == floor)
+    }
+
+    pub fn time_on_floors(&self) -> std::collections::BTreeMap<isize, usize> {
+        let mut time = std::collections::BTreeMap::new();
+        for &floor in &self.history[..self.history.len() - 1] {
+            *time.entry(floor).or_insert(0) += 1;
+        }
+
+        time
+    }
+}
+
+impl std::fmt::Display for FloorTrace {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(f, "Final floor: {}", self.final_floor())?;
+        for (name, floor) in &[("Lowest", self.min_floor()), ("Highest", self.max_floor())] {
+            writeln!(
+                f,
+                "{} floor: {} (first reached at {})",
+                name,
+                floor,
+                self.first_reached(*floor).unwrap()
+            )?;
+        }
+        write!(f, "Time on floors:")?;
+        for (floor, time) in self.time_on_floors() {
+            write!(f, "\n{}: {}", floor, time)?;
+        }
+
+        Ok(())
+    }
+}
+
+pub fn trace(input: impl std::io::BufRead, strict: bool) -> Result<FloorTrace, TraceError> {
+    let directions: Vec<u8> = input.bytes().map(|c| c.unwrap()).collect();
+    FloorTrace::from_bytes(&directions, strict)
+}
+
 pub fn solve_a<I: std::io::BufRead>(input: I) -> isize {
     input.bytes().map(|c| direction_to_step(c.unwrap())).sum()
 }
 
-pub fn solve_b<I: std::io::BufRead>(input: I) -> usize {
+pub fn solve_b<I: std::io::BufRead>(input: I) -> Option<usize> {
     input
         .bytes()
         .scan(0, |state, c| {
-            *state = *state + direction_to_step(c.unwrap());
-            if *state != -1 {
-                Some(*state)
-            } else {
-                None
-            }
+            *state += direction_to_step(c.unwrap());
+            Some(*state)
         })
-        .count()
-        + 1
+        .position(|floor| floor == -1)
+        .map(|i| i + 1)
 }
 
 #[cfg(test)]
@@ -44,7 +139,64 @@ mod tests {
 
     #[test]
     fn check_b() {
-        assert_eq!(1, solve_b(&b")"[..]));
-        assert_eq!(5, solve_b(&b"()())"[..]));
+        assert_eq!(Some(1), solve_b(&b")"[..]));
+        assert_eq!(Some(5), solve_b(&b"()())"[..]));
+        assert_eq!(None, solve_b(&b"(()(("[..]));
+        assert_eq!(None, solve_b(&b""[..]));
+    }
+
+    #[test]
+    fn check_trace() {
+        let floors = FloorTrace::from_bytes(b"(()))x)((", false).unwrap();
+        assert_eq!(&[0, 1, 2, 1, 0, -1, -1, -2, -1, 0], floors.history());
+        assert_eq!(0, floors.final_floor());
+        assert_eq!(-2, floors.min_floor());
+        assert_eq!(2, floors.max_floor());
+        assert_eq!(Some(0), floors.first_reached(0));
+        assert_eq!(Some(5), floors.first_reached(-1));
+        assert_eq!(Some(7), floors.first_reached(-2));
+        assert_eq!(None, floors.first_reached(3));
+
+        let time: Vec<(isize, usize)> = floors.time_on_floors().into_iter().collect();
+        assert_eq!(vec![(-2, 1), (-1, 3), (0, 2), (1, 2), (2, 1)], time);
+
+        assert_eq!(
+            "Final floor: 0\n\
+             Lowest floor: -2 (first reached at 7)\n\
+             Highest floor: 2 (first reached at 2)\n\
+             Time on floors:\n-2: 1\n-1: 3\n0: 2\n1: 2\n2: 1",
+            floors.to_string()
+        );
+
+        let empty = FloorTrace::from_bytes(b"", true).unwrap();
+        assert_eq!(&[0], empty.history());
+        assert!(empty.time_on_floors().is_empty());
+
+        for input in &[&b"(()(()("[..], b"))(((((", b")())())"] {
+            let final_floor = trace(&input[..], false).unwrap().final_floor();
+            assert_eq!(solve_a(&input[..]), final_floor);
+        }
+    }
+
+    #[test]
+    fn check_strict() {
+        assert_eq!(
+            Err(TraceError::UnexpectedCharacter {
+                position: 6,
+                c: b'x'
+            }),
+            FloorTrace::from_bytes(b"(()))x)((", true)
+        );
+        assert_eq!(
+            "Unexpected character '\\n' at position 2",
+            FloorTrace::from_bytes(b"(\n)", true)
+                .unwrap_err()
+                .to_string()
+        );
+
+        let strict = FloorTrace::from_bytes(b"(()\n", true).unwrap();
+        assert_eq!(&[0, 1, 2, 1], strict.history());
+        let lenient = FloorTrace::from_bytes(b"(()\n", false).unwrap();
+        assert_eq!(&[0, 1, 2, 1, 1], lenient.history());
     }
 }
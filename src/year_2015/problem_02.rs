@@ -1,62 +1,287 @@
 use crate::utils::parsing::parse_decimal;
 use nom::character::complete::char;
+use std::collections::BTreeMap;
 
 type Dim = u64;
 
-#[derive(Debug, PartialEq, Eq)]
-struct Dims(Dim, Dim, Dim);
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dims(pub Dim, pub Dim, pub Dim);
 
-fn parse_dims(input: &str) -> Dims {
-    fn parse_dims_impl(input: &str) -> nom::IResult<&str, Dims> {
-        let (input, length) = parse_decimal::<Dim>(input)?;
+impl Dims {
+    pub fn volume(&self) -> Option<Dim> {
+        self.0.checked_mul(self.1)?.checked_mul(self.2)
+    }
+}
+
+impl std::fmt::Display for Dims {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}x{}x{}", self.0, self.1, self.2)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DimsError {
+    InvalidSyntax {
+        line: usize,
+        text: String,
+    },
+    NonPositive {
+        line: usize,
+        dimension: &'static str,
+        value: i64,
+    },
+    TooLarge {
+        line: usize,
+    },
+}
+
+impl std::fmt::Display for DimsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            DimsError::InvalidSyntax { line, text } => {
+                write!(f, "Line {}: invalid dimensions '{}'", line, text)
+            }
+            DimsError::NonPositive {
+                line,
+                dimension,
+                value,
+            } => write!(
+                f,
+                "Line {}: {} must be positive, got {}",
+                line, dimension, value
+            ),
+            DimsError::TooLarge { line } => write!(f, "Line {}: dimensions are too large", line),
+        }
+    }
+}
+
+fn parse_dims(input: &str, line: usize) -> Result<Dims, DimsError> {
+    fn parse_dims_impl(input: &str) -> nom::IResult<&str, (i64, i64, i64)> {
+        let (input, length) = parse_decimal::<i64>(input)?;
         let (input, _) = char('x')(input)?;
-        let (input, width) = parse_decimal::<Dim>(input)?;
+        let (input, width) = parse_decimal::<i64>(input)?;
         let (input, _) = char('x')(input)?;
-        let (input, height) = parse_decimal::<Dim>(input)?;
+        let (input, height) = parse_decimal::<i64>(input)?;
 
-        Ok((input, Dims(length, width, height)))
+        Ok((input, (length, width, height)))
     }
 
-    match nom::combinator::all_consuming(parse_dims_impl)(input) {
+    let (length, width, height) = match nom::combinator::all_consuming(parse_dims_impl)(input) {
         Ok((_, dims)) => dims,
-        Err(e) => panic!(format!("Failed to parse dims: {:?}", e)),
+        Err(_) => {
+            return Err(DimsError::InvalidSyntax {
+                line,
+                text: input.to_string(),
+            })
+        }
+    };
+
+    let positive = |dimension, value: i64| {
+        if value > 0 {
+            Ok(value as Dim)
+        } else {
+            Err(DimsError::NonPositive {
+                line,
+                dimension,
+                value,
+            })
+        }
+    };
+
+    Ok(Dims(
+        positive("length", length)?,
+        positive("width", width)?,
+        positive("height", height)?,
+    ))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PresentOrder {
+    pub line: usize,
+    pub dims: Dims,
+    pub surface_area: Dim,
+    pub slack: Dim,
+    pub ribbon: Dim,
+    pub bow: Dim,
+}
+
+impl PresentOrder {
+    pub fn new(line: usize, dims: Dims) -> Result<Self, DimsError> {
+        let order = || {
+            let Dims(l, w, h) = dims;
+            let sides = [l.checked_mul(w)?, w.checked_mul(h)?, h.checked_mul(l)?];
+            let mut edges = [l, w, h];
+            edges.sort();
+
+            let order = PresentOrder {
+                line,
+                dims,
+                surface_area: sides
+                    .iter()
+                    .try_fold(0 as Dim, |sum, s| sum.checked_add(s.checked_mul(2)?))?,
+                slack: *sides.iter().min().unwrap(),
+                ribbon: edges[0].checked_add(edges[1])?.checked_mul(2)?,
+                bow: dims.volume()?,
+            };
+            order.surface_area.checked_add(order.slack)?;
+            order.ribbon.checked_add(order.bow)?;
+
+            Some(order)
+        };
+
+        order().ok_or(DimsError::TooLarge { line })
+    }
+
+    pub fn paper(&self) -> Dim {
+        self.surface_area + self.slack
+    }
+
+    pub fn total_ribbon(&self) -> Dim {
+        self.ribbon + self.bow
+    }
+
+    fn csv_header() -> &'static str {
+        "line,length,width,height,surface_area,slack,paper,ribbon,bow,total_ribbon"
+    }
+
+    fn to_csv(&self) -> String {
+        let Dims(l, w, h) = self.dims;
+        format!(
+            "{},{},{},{},{},{},{},{},{},{}",
+            self.line,
+            l,
+            w,
+            h,
+            self.surface_area,
+            self.slack,
+            self.paper(),
+            self.ribbon,
+            self.bow,
+            self.total_ribbon()
+        )
+    }
+}
+
+impl std::fmt::Display for PresentOrder {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: surface area {}, slack {}, paper {}, ribbon {}, bow {}, total ribbon {}",
+            self.dims,
+            self.surface_area,
+            self.slack,
+            self.paper(),
+            self.ribbon,
+            self.bow,
+            self.total_ribbon()
+        )
     }
 }
 
-fn calc_area_a(Dims(l, w, h): Dims) -> Dim {
-    let sides = [l * w, w * h, h * l];
-    let min_side = sides.iter().min().unwrap();
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrderStats {
+    pub presents: usize,
+    pub paper: Dim,
+    pub ribbon: Dim,
+    pub largest: Option<PresentOrder>,
+    pub volume_histogram: BTreeMap<Dim, usize>,
+}
 
-    let box_area: Dim = sides.iter().map(|s| s * 2).sum();
-    box_area + min_side
+fn volume_bucket(volume: Dim) -> Dim {
+    1 << (Dim::BITS - 1 - volume.leading_zeros())
 }
 
-fn calc_area_b(Dims(l, w, h): Dims) -> Dim {
-    let mut sides = [l, w, h];
-    sides.sort();
+fn total(orders: &[PresentOrder], amount: fn(&PresentOrder) -> Dim) -> Result<Dim, DimsError> {
+    orders.iter().try_fold(0, |total: Dim, order| {
+        total
+            .checked_add(amount(order))
+            .ok_or(DimsError::TooLarge { line: order.line })
+    })
+}
 
-    let wrap_ribbon = sides[0] * 2 + sides[1] * 2;
-    let bow_ribbon = l * w * h;
+impl OrderStats {
+    pub fn new(orders: &[PresentOrder]) -> Result<Self, DimsError> {
+        let mut volume_histogram = BTreeMap::new();
+        for order in orders {
+            *volume_histogram
+                .entry(volume_bucket(order.bow))
+                .or_insert(0) += 1;
+        }
 
-    wrap_ribbon + bow_ribbon
+        Ok(OrderStats {
+            presents: orders.len(),
+            paper: total(orders, PresentOrder::paper)?,
+            ribbon: total(orders, PresentOrder::total_ribbon)?,
+            largest: orders.iter().rev().max_by_key(|order| order.bow).cloned(),
+            volume_histogram,
+        })
+    }
 }
 
-fn solve(input: impl std::io::BufRead, calc_area: fn(Dims) -> Dim) -> Dim {
+impl std::fmt::Display for OrderStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(f, "Presents: {}", self.presents)?;
+        writeln!(f, "Paper: {}", self.paper)?;
+        writeln!(f, "Ribbon: {}", self.ribbon)?;
+        if let Some(largest) = &self.largest {
+            writeln!(
+                f,
+                "Largest present: {} on line {} (volume {})",
+                largest.dims, largest.line, largest.bow
+            )?;
+        }
+        write!(f, "Volume histogram:")?;
+        for (&low, count) in &self.volume_histogram {
+            write!(f, "\n{}-{}: {}", low, low + (low - 1), count)?;
+        }
+
+        Ok(())
+    }
+}
+
+pub fn parse_orders(input: impl std::io::BufRead) -> Result<Vec<PresentOrder>, DimsError> {
     input
         .lines()
-        .map(|line| {
-            let dims = parse_dims(&line.unwrap());
-            calc_area(dims)
+        .enumerate()
+        .map(|(n, line)| {
+            let dims = parse_dims(&line.unwrap(), n + 1)?;
+            PresentOrder::new(n + 1, dims)
         })
-        .sum()
+        .collect()
+}
+
+pub fn report(input: impl std::io::BufRead) -> Result<String, DimsError> {
+    let orders = parse_orders(input)?;
+    let stats = OrderStats::new(&orders)?;
+
+    Ok(orders
+        .iter()
+        .map(|order| format!("Line {}: {}", order.line, order))
+        .chain(std::iter::once(stats.to_string()))
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+pub fn report_csv(input: impl std::io::BufRead) -> Result<String, DimsError> {
+    Ok(std::iter::once(PresentOrder::csv_header().to_string())
+        .chain(parse_orders(input)?.iter().map(PresentOrder::to_csv))
+        .collect::<Vec<_>>()
+        .join("\n"))
 }
 
-pub fn solve_a(input: impl std::io::BufRead) -> Dim {
-    solve(input, calc_area_a)
+fn solve(
+    input: impl std::io::BufRead,
+    calc_area: fn(&PresentOrder) -> Dim,
+) -> Result<Dim, DimsError> {
+    total(&parse_orders(input)?, calc_area)
 }
 
-pub fn solve_b(input: impl std::io::BufRead) -> Dim {
-    solve(input, calc_area_b)
+pub fn solve_a(input: impl std::io::BufRead) -> Result<Dim, DimsError> {
+    solve(input, PresentOrder::paper)
+}
+
+pub fn solve_b(input: impl std::io::BufRead) -> Result<Dim, DimsError> {
+    solve(input, PresentOrder::total_ribbon)
 }
 
 #[cfg(test)]
@@ -65,18 +290,144 @@ mod tests {
 
     #[test]
     fn check_parsing() {
-        assert_eq!(Dims(1, 22, 333), parse_dims("1x22x333"));
+        assert_eq!(Ok(Dims(1, 22, 333)), parse_dims("1x22x333", 1));
+        assert_eq!(
+            Err(DimsError::InvalidSyntax {
+                line: 2,
+                text: String::from("1x22")
+            }),
+            parse_dims("1x22", 2)
+        );
+        assert_eq!(
+            Err(DimsError::NonPositive {
+                line: 3,
+                dimension: "width",
+                value: -2
+            }),
+            parse_dims("1x-2x3", 3)
+        );
+        assert_eq!(
+            Err(DimsError::NonPositive {
+                line: 4,
+                dimension: "height",
+                value: 0
+            }),
+            parse_dims("1x2x0", 4)
+        );
+        assert_eq!(
+            Err(DimsError::NonPositive {
+                line: 3,
+                dimension: "length",
+                value: 0
+            }),
+            parse_orders(&b"2x3x4\n1x1x10\n0x1x1"[..])
+        );
+        assert_eq!(
+            "Line 3: width must be positive, got -2",
+            parse_dims("1x-2x3", 3).unwrap_err().to_string()
+        );
     }
 
     #[test]
     fn check_a() {
-        assert_eq!(58, solve_a(&b"2x3x4"[..]));
-        assert_eq!(43, solve_a(&b"1x1x10"[..]));
+        assert_eq!(Ok(58), solve_a(&b"2x3x4"[..]));
+        assert_eq!(Ok(43), solve_a(&b"1x1x10"[..]));
     }
 
     #[test]
     fn check_b() {
-        assert_eq!(34, solve_b(&b"2x3x4"[..]));
-        assert_eq!(14, solve_b(&b"1x1x10"[..]));
+        assert_eq!(Ok(34), solve_b(&b"2x3x4"[..]));
+        assert_eq!(Ok(14), solve_b(&b"1x1x10"[..]));
+    }
+
+    #[test]
+    fn check_invalid_input() {
+        assert_eq!(
+            "Line 2: invalid dimensions '2x3'",
+            solve_a(&b"1x1x1\n2x3"[..]).unwrap_err().to_string()
+        );
+        assert_eq!(
+            Err(DimsError::TooLarge { line: 1 }),
+            solve_b(&b"4294967296x4294967296x4294967296"[..])
+        );
+        assert_eq!(
+            Err(DimsError::TooLarge { line: 2 }),
+            report(&b"1x1x4611686018427387903\n1x1x4611686018427387903"[..])
+        );
+    }
+
+    #[test]
+    fn check_too_large() {
+        assert_eq!(
+            Err(DimsError::TooLarge { line: 2 }),
+            parse_orders(&b"1x1x1\n4294967296x4294967296x1"[..])
+        );
+        assert_eq!(
+            Err(DimsError::TooLarge { line: 1 }),
+            PresentOrder::new(1, Dims(Dim::MAX, 1, 1))
+        );
+        assert_eq!(
+            "Line 3: dimensions are too large",
+            DimsError::TooLarge { line: 3 }.to_string()
+        );
+
+        let input = b"1x1x4611686018427387903\n1x1x4611686018427387903";
+        let orders = parse_orders(&input[..]).unwrap();
+        assert_eq!(Dim::MAX, orders[0].paper());
+        assert_eq!(
+            Err(DimsError::TooLarge { line: 2 }),
+            total(&orders, PresentOrder::paper)
+        );
+        assert_eq!(
+            Err(DimsError::TooLarge { line: 2 }),
+            OrderStats::new(&orders)
+        );
+    }
+
+    #[test]
+    fn check_report() {
+        let order = PresentOrder::new(1, Dims(2, 3, 4)).unwrap();
+        assert_eq!(
+            (52, 6, 58),
+            (order.surface_area, order.slack, order.paper())
+        );
+        assert_eq!(
+            (10, 24, 34),
+            (order.ribbon, order.bow, order.total_ribbon())
+        );
+
+        let orders = parse_orders(&b"2x3x4\n1x1x10\n4x3x2\n1x1x1"[..]).unwrap();
+        let stats = OrderStats::new(&orders).unwrap();
+        assert_eq!(4, stats.presents);
+        assert_eq!(58 + 43 + 58 + 7, stats.paper);
+        assert_eq!(34 + 14 + 34 + 5, stats.ribbon);
+        assert_eq!(Some(1), stats.largest.map(|order| order.line));
+        assert_eq!(
+            vec![(1, 1), (8, 1), (16, 2)],
+            stats.volume_histogram.into_iter().collect::<Vec<_>>()
+        );
+
+        assert_eq!(
+            "Line 1: 2x3x4: surface area 52, slack 6, paper 58, ribbon 10, bow 24, total ribbon 34\n\
+             Line 2: 1x1x10: surface area 42, slack 1, paper 43, ribbon 4, bow 10, total ribbon 14\n\
+             Presents: 2\n\
+             Paper: 101\n\
+             Ribbon: 48\n\
+             Largest present: 2x3x4 on line 1 (volume 24)\n\
+             Volume histogram:\n\
+             8-15: 1\n\
+             16-31: 1",
+            report(&b"2x3x4\n1x1x10"[..]).unwrap()
+        );
+        assert_eq!(
+            "line,length,width,height,surface_area,slack,paper,ribbon,bow,total_ribbon\n\
+             1,2,3,4,52,6,58,10,24,34\n\
+             2,1,1,10,42,1,43,4,10,14",
+            report_csv(&b"2x3x4\n1x1x10"[..]).unwrap()
+        );
+        assert_eq!(
+            "Presents: 0\nPaper: 0\nRibbon: 0\nVolume histogram:",
+            report(&b""[..]).unwrap()
+        );
     }
 }
@@ -23,31 +23,240 @@ fn inc_number(mut digits: Vec<u8>) -> Vec<u8> {
     digits
 }
 
-pub fn solve(input: impl std::io::BufRead, leading_zeroes: usize) -> usize {
-    let check = match leading_zeroes {
-        5 => |x: u8| x <= 15,
-        6 => |x: u8| x == 0,
-        _ => panic!("unexpected"),
-    };
+const BATCH_SIZE: usize = 4096;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    Md5,
+    Sha1,
+    Sha256,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HashPrefix {
+    nibbles: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Target {
+    algorithm: HashAlgorithm,
+    prefix: HashPrefix,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TargetError {
+    UnknownAlgorithm(String),
+    InvalidPrefix(String),
+    PrefixTooLong {
+        algorithm: HashAlgorithm,
+        nibbles: usize,
+    },
+}
+
+impl std::fmt::Display for TargetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            TargetError::UnknownAlgorithm(name) => write!(f, "Unknown hash algorithm: {}", name),
+            TargetError::InvalidPrefix(prefix) => write!(f, "Invalid hex prefix: {}", prefix),
+            TargetError::PrefixTooLong { algorithm, nibbles } => write!(
+                f,
+                "Prefix of {} digits is longer than the {} digest ({} digits)",
+                nibbles,
+                algorithm,
+                algorithm.digest_nibbles()
+            ),
+        }
+    }
+}
+
+impl std::str::FromStr for HashAlgorithm {
+    type Err = TargetError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "md5" => Ok(HashAlgorithm::Md5),
+            "sha1" => Ok(HashAlgorithm::Sha1),
+            "sha256" => Ok(HashAlgorithm::Sha256),
+            _ => Err(TargetError::UnknownAlgorithm(name.to_string())),
+        }
+    }
+}
+
+impl std::fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            HashAlgorithm::Md5 => write!(f, "md5"),
+            HashAlgorithm::Sha1 => write!(f, "sha1"),
+            HashAlgorithm::Sha256 => write!(f, "sha256"),
+        }
+    }
+}
+
+impl HashAlgorithm {
+    pub fn digest_nibbles(&self) -> usize {
+        match self {
+            HashAlgorithm::Md5 => 32,
+            HashAlgorithm::Sha1 => 40,
+            HashAlgorithm::Sha256 => 64,
+        }
+    }
+}
+
+impl std::str::FromStr for HashPrefix {
+    type Err = TargetError;
 
-    let mut key: Vec<u8> = input.bytes().map(|c| c.unwrap()).collect();
+    fn from_str(prefix: &str) -> Result<Self, Self::Err> {
+        let nibbles = prefix
+            .chars()
+            .map(|c| c.to_digit(16).map(|d| d as u8))
+            .collect::<Option<Vec<u8>>>()
+            .ok_or_else(|| TargetError::InvalidPrefix(prefix.to_string()))?;
+
+        Ok(HashPrefix { nibbles })
+    }
+}
+
+impl HashPrefix {
+    pub fn zeroes(count: usize) -> Self {
+        HashPrefix {
+            nibbles: vec![0; count],
+        }
+    }
+
+    fn matches(&self, hash: &[u8]) -> bool {
+        self.nibbles.len() <= hash.len() * 2
+            && self.nibbles.iter().enumerate().all(|(i, &nibble)| {
+                let byte = hash[i / 2];
+                let digit = if i % 2 == 0 { byte >> 4 } else { byte & 0x0f };
+                digit == nibble
+            })
+    }
+}
+
+impl Target {
+    pub fn new(algorithm: HashAlgorithm, prefix: HashPrefix) -> Result<Self, TargetError> {
+        if prefix.nibbles.len() > algorithm.digest_nibbles() {
+            return Err(TargetError::PrefixTooLong {
+                algorithm,
+                nibbles: prefix.nibbles.len(),
+            });
+        }
+
+        Ok(Target { algorithm, prefix })
+    }
+
+    pub fn zeroes(leading_zeroes: usize) -> Result<Self, TargetError> {
+        Target::new(HashAlgorithm::Md5, HashPrefix::zeroes(leading_zeroes))
+    }
+}
+
+fn mine_batch<D: Digest>(
+    key: &[u8],
+    batch: std::ops::Range<usize>,
+    prefix: &HashPrefix,
+) -> Option<usize> {
+    let mut key = key.to_vec();
     let key_len = key.len();
-    let mut n = vec![b'1'];
-    let mut hasher = md5::Md5::new();
+    let mut n = batch.start.to_string().into_bytes();
+    let mut hasher = D::new();
 
-    loop {
+    for nonce in batch {
         key.extend_from_slice(&n);
         hasher.update(&key);
 
         let result = hasher.finalize_reset();
-        if result[0] == 0 && result[1] == 0 && check(result[2]) {
-            let num_str = std::str::from_utf8(&n).unwrap();
-            return num_str.parse::<usize>().unwrap();
+        if prefix.matches(&result) {
+            return Some(nonce);
         }
 
         key.truncate(key_len);
         n = inc_number(n);
     }
+
+    None
+}
+
+fn mine_target_batch(key: &[u8], batch: std::ops::Range<usize>, target: &Target) -> Option<usize> {
+    match target.algorithm {
+        HashAlgorithm::Md5 => mine_batch::<md5::Md5>(key, batch, &target.prefix),
+        HashAlgorithm::Sha1 => mine_batch::<sha1::Sha1>(key, batch, &target.prefix),
+        HashAlgorithm::Sha256 => mine_batch::<sha2::Sha256>(key, batch, &target.prefix),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MiningStats {
+    pub nonce: usize,
+    pub hashes: usize,
+    pub elapsed: std::time::Duration,
+}
+
+impl MiningStats {
+    pub fn hashes_per_second(&self) -> f64 {
+        self.hashes as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+}
+
+impl std::fmt::Display for MiningStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(f, "{}", self.nonce)?;
+        write!(
+            f,
+            "{} hashes in {:.3}s ({:.0} hashes/s)",
+            self.hashes,
+            self.elapsed.as_secs_f64(),
+            self.hashes_per_second()
+        )
+    }
+}
+
+pub fn mine(key: &[u8], target: &Target, threads: usize) -> MiningStats {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let next_batch = AtomicUsize::new(1);
+    let best = AtomicUsize::new(usize::MAX);
+    let hashes = AtomicUsize::new(0);
+    let started = std::time::Instant::now();
+
+    std::thread::scope(|scope| {
+        for _ in 0..threads.max(1) {
+            scope.spawn(|| loop {
+                let start = next_batch.fetch_add(BATCH_SIZE, Ordering::Relaxed);
+                if start >= best.load(Ordering::Relaxed) {
+                    break;
+                }
+
+                let found = mine_target_batch(key, start..start + BATCH_SIZE, target);
+                hashes.fetch_add(
+                    found.map_or(BATCH_SIZE, |nonce| nonce - start + 1),
+                    Ordering::Relaxed,
+                );
+                if let Some(nonce) = found {
+                    best.fetch_min(nonce, Ordering::Relaxed);
+                }
+            });
+        }
+    });
+
+    MiningStats {
+        nonce: best.into_inner(),
+        hashes: hashes.into_inner(),
+        elapsed: started.elapsed(),
+    }
+}
+
+pub fn default_threads() -> usize {
+    std::thread::available_parallelism().map_or(1, usize::from)
+}
+
+pub fn mine_input(input: impl std::io::BufRead, target: &Target, threads: usize) -> MiningStats {
+    let key: Vec<u8> = input.bytes().map(|c| c.unwrap()).collect();
+    mine(&key, target, threads)
+}
+
+pub fn solve(input: impl std::io::BufRead, leading_zeroes: usize) -> usize {
+    let target = Target::zeroes(leading_zeroes).unwrap();
+    mine_input(input, &target, default_threads()).nonce
 }
 
 pub fn solve_a(input: impl std::io::BufRead) -> usize {
@@ -76,4 +285,68 @@ mod test {
         assert_eq!(43, solve_a(&b"abcdef6090"[..]));
         assert_eq!(70, solve_a(&b"pqrstuv10489"[..]));
     }
+
+    #[test]
+    fn check_mine() {
+        for threads in 1..=4 {
+            let stats = mine(b"abcdef6090", &Target::zeroes(5).unwrap(), threads);
+            assert_eq!(43, stats.nonce);
+            assert!(stats.hashes >= 43);
+        }
+
+        let prefix = HashPrefix::zeroes(5);
+        assert_eq!(
+            Some(70),
+            mine_batch::<md5::Md5>(b"pqrstuv10489", 1..100, &prefix)
+        );
+        assert_eq!(None, mine_batch::<md5::Md5>(b"abcdef6090", 1..43, &prefix));
+    }
+
+    #[test]
+    fn check_targets() {
+        let target = |algorithm: &str, prefix: &str| {
+            Target::new(algorithm.parse().unwrap(), prefix.parse().unwrap()).unwrap()
+        };
+        let mine = |target: &Target| mine(b"abcdef6090", target, 2).nonce;
+
+        assert_eq!(35, mine(&Target::zeroes(1).unwrap()));
+        assert_eq!(43, mine(&Target::zeroes(3).unwrap()));
+        assert_eq!(718, mine(&target("md5", "abc")));
+        assert_eq!(718, mine(&target("md5", "ABC")));
+        assert_eq!(21443, mine(&target("sha1", "0000")));
+        assert_eq!(16975, mine(&target("sha256", "0000")));
+        assert_eq!(60427, mine(&target("sha256", "dead")));
+
+        assert!(HashPrefix::zeroes(0).matches(&[0xff]));
+        assert!(HashPrefix::zeroes(3).matches(&[0x00, 0x0f]));
+        assert!(!HashPrefix::zeroes(3).matches(&[0x00, 0x10]));
+        assert!(!HashPrefix::zeroes(3).matches(&[0x00]));
+
+        assert_eq!(
+            Err(TargetError::UnknownAlgorithm(String::from("crc32"))),
+            "crc32".parse::<HashAlgorithm>()
+        );
+        assert_eq!(
+            Err(TargetError::InvalidPrefix(String::from("0x1"))),
+            "0x1".parse::<HashPrefix>()
+        );
+
+        assert!(Target::zeroes(32).is_ok());
+        assert_eq!(
+            Err(TargetError::PrefixTooLong {
+                algorithm: HashAlgorithm::Md5,
+                nibbles: 33
+            }),
+            Target::zeroes(33)
+        );
+        assert!(Target::new(HashAlgorithm::Sha1, HashPrefix::zeroes(40)).is_ok());
+        assert!(Target::new(HashAlgorithm::Sha1, HashPrefix::zeroes(41)).is_err());
+        assert!(Target::new(HashAlgorithm::Sha256, HashPrefix::zeroes(64)).is_ok());
+        assert_eq!(
+            "Prefix of 65 digits is longer than the sha256 digest (64 digits)",
+            Target::new(HashAlgorithm::Sha256, HashPrefix::zeroes(65))
+                .unwrap_err()
+                .to_string()
+        );
+    }
 }
@@ -1,91 +1,319 @@
-static NAUGHTY: [&[u8]; 4] = [b"ab", b"cd", b"pq", b"xy"];
+use crate::utils::parsing::{parse_decimal, parse_ws};
 
-fn is_vowel(c: &u8) -> bool {
-    let vowels = b"aeiou";
-    vowels.contains(&c)
+type NomResult<'a, T> = nom::IResult<&'a str, T>;
+
+const PART_A_RULES: &str = r#"
+    three-vowels = count("aeiou", 3)
+    letter-twice-in-a-row = pattern("xx")
+    no-naughty-strings = not(any(contains("ab"), contains("cd"), contains("pq"), contains("xy")))
+"#;
+
+const PART_B_RULES: &str = r#"
+    pair-of-letters-twice = pattern("xy*xy")
+    double-letter-with-a-letter-between = pattern("x?x")
+"#;
+
+pub trait StringRule {
+    fn is_match(&self, cs: &[u8]) -> bool;
+}
+
+pub struct All(pub Vec<Box<dyn StringRule>>);
+pub struct Any(pub Vec<Box<dyn StringRule>>);
+pub struct Not(pub Box<dyn StringRule>);
+pub struct Contains(pub Vec<u8>);
+
+pub struct CountAtLeast {
+    pub chars: Vec<u8>,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatternItem {
+    Var(u8),
+    AnyChar,
+    Gap,
+}
+
+pub struct Pattern(pub Vec<PatternItem>);
+
+impl StringRule for All {
+    fn is_match(&self, cs: &[u8]) -> bool {
+        self.0.iter().all(|rule| rule.is_match(cs))
+    }
+}
+
+impl StringRule for Any {
+    fn is_match(&self, cs: &[u8]) -> bool {
+        self.0.iter().any(|rule| rule.is_match(cs))
+    }
+}
+
+impl StringRule for Not {
+    fn is_match(&self, cs: &[u8]) -> bool {
+        !self.0.is_match(cs)
+    }
 }
 
-fn has_three_vowels(cs: &[u8]) -> bool {
-    cs.iter().filter(|c| is_vowel(c)).count() >= 3
+impl StringRule for Contains {
+    fn is_match(&self, cs: &[u8]) -> bool {
+        self.0.is_empty() || cs.windows(self.0.len()).any(|w| w == &self.0[..])
+    }
 }
 
-fn has_letter_twice_in_a_row(cs: &[u8]) -> bool {
-    cs.windows(2).any(|w| match w {
-        [a, b] => a == b,
-        _ => false,
-    })
+impl StringRule for CountAtLeast {
+    fn is_match(&self, cs: &[u8]) -> bool {
+        cs.iter().filter(|c| self.chars.contains(c)).count() >= self.count
+    }
 }
 
-fn has_no_naughty_strings(cs: &[u8]) -> bool {
-    cs.windows(2).all(|w| !NAUGHTY.contains(&w))
+fn match_pattern_prefix(pattern: &[PatternItem], cs: &[u8], vars: &mut [Option<u8>; 26]) -> bool {
+    match pattern {
+        [] => true,
+        [PatternItem::Gap, rest @ ..] => {
+            (0..=cs.len()).any(|skip| match_pattern_prefix(rest, &cs[skip..], vars))
+        }
+        [item, rest @ ..] => match (item, cs) {
+            (_, []) => false,
+            (PatternItem::AnyChar, [_, cs_rest @ ..]) => match_pattern_prefix(rest, cs_rest, vars),
+            (PatternItem::Var(var), [c, cs_rest @ ..]) => {
+                let var = (var - b'a') as usize;
+                match vars[var] {
+                    Some(bound) if bound != *c => false,
+                    Some(_) => match_pattern_prefix(rest, cs_rest, vars),
+                    None => {
+                        vars[var] = Some(*c);
+                        let is_match = match_pattern_prefix(rest, cs_rest, vars);
+                        vars[var] = None;
+                        is_match
+                    }
+                }
+            }
+            (PatternItem::Gap, _) => unreachable!(),
+        },
+    }
 }
 
-fn has_pair_of_letters_twice(cs: &[u8]) -> bool {
-    match cs {
-        [] => false,
-        [a, b, rest @ ..] if rest.windows(2).any(|w| w == [*a, *b]) => true,
-        [_, rest @ ..] => has_pair_of_letters_twice(rest),
+impl StringRule for Pattern {
+    fn is_match(&self, cs: &[u8]) -> bool {
+        (0..=cs.len()).any(|start| match_pattern_prefix(&self.0, &cs[start..], &mut [None; 26]))
     }
 }
 
-fn has_double_letter_with_a_letter_between(cs: &[u8]) -> bool {
-    cs.windows(3).any(|w| match w {
-        [a, _, c, ..] => a == c,
-        _ => false,
-    })
+fn parse_pattern(pattern: &str) -> Pattern {
+    let items = pattern
+        .bytes()
+        .map(|c| match c {
+            b'a'..=b'z' => PatternItem::Var(c),
+            b'?' => PatternItem::AnyChar,
+            b'*' => PatternItem::Gap,
+            _ => panic!("Invalid pattern: {}", pattern),
+        })
+        .collect();
+
+    Pattern(items)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Expr {
+    Call(String, Vec<Expr>),
+    Str(String),
+    Number(usize),
+}
+
+fn parse_name(input: &str) -> NomResult<'_, String> {
+    use nom::{bytes::complete::take_while1, combinator::map};
+
+    let name = take_while1(|c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    map(parse_ws(name), str::to_string)(input)
+}
+
+fn parse_expr(input: &str) -> NomResult<'_, Expr> {
+    use nom::{
+        branch::alt,
+        bytes::complete::take_until,
+        character::complete::{char, multispace0},
+        combinator::map,
+        multi::separated_list0,
+        sequence::{delimited, pair},
+    };
+
+    let string = delimited(char('"'), take_until("\""), char('"'));
+    let args = delimited(
+        char('('),
+        separated_list0(char(','), parse_expr),
+        parse_ws(char(')')),
+    );
+
+    let expr = alt((
+        map(string, |s: &str| Expr::Str(s.to_string())),
+        map(parse_decimal, Expr::Number),
+        map(pair(parse_name, args), |(name, args)| {
+            Expr::Call(name, args)
+        }),
+    ));
+    delimited(multispace0, expr, multispace0)(input)
+}
+
+fn build_rule(expr: &Expr) -> Box<dyn StringRule> {
+    let rules = |args: &[Expr]| args.iter().map(build_rule).collect();
+
+    match expr {
+        Expr::Call(name, args) => match (name.as_str(), &args[..]) {
+            ("all", args) => Box::new(All(rules(args))),
+            ("any", args) => Box::new(Any(rules(args))),
+            ("not", [arg]) => Box::new(Not(build_rule(arg))),
+            ("contains", [Expr::Str(s)]) => Box::new(Contains(s.as_bytes().to_vec())),
+            ("count", [Expr::Str(s), Expr::Number(count)]) => Box::new(CountAtLeast {
+                chars: s.as_bytes().to_vec(),
+                count: *count,
+            }),
+            ("pattern", [Expr::Str(s)]) => Box::new(parse_pattern(s)),
+            _ => panic!("Invalid rule: {}({:?})", name, args),
+        },
+        _ => panic!("Expected a rule, found: {:?}", expr),
+    }
 }
 
-fn solve(input: impl std::io::BufRead, is_naughty: fn(&[u8]) -> bool) -> usize {
+pub struct RuleSet {
+    rules: Vec<(String, Box<dyn StringRule>)>,
+}
+
+impl RuleSet {
+    pub fn new() -> Self {
+        RuleSet { rules: Vec::new() }
+    }
+
+    pub fn with_rule(mut self, name: &str, rule: Box<dyn StringRule>) -> Self {
+        self.rules.push((name.to_string(), rule));
+        self
+    }
+
+    pub fn parse(input: &str) -> Self {
+        use nom::{character::complete::char, combinator::all_consuming, sequence::separated_pair};
+
+        input
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .fold(RuleSet::new(), |rules, line| {
+                let mut rule = all_consuming(separated_pair(parse_name, char('='), parse_expr));
+                match rule(line) {
+                    Ok((_, (name, expr))) => rules.with_rule(&name, build_rule(&expr)),
+                    Err(e) => panic!("Failed to parse rule: {:?}", e),
+                }
+            })
+    }
+
+    pub fn is_nice(&self, cs: &[u8]) -> bool {
+        self.rules.iter().all(|(_, rule)| rule.is_match(cs))
+    }
+
+    pub fn explain(&self, cs: &[u8]) -> Vec<(&str, bool)> {
+        self.rules
+            .iter()
+            .map(|(name, rule)| (name.as_str(), rule.is_match(cs)))
+            .collect()
+    }
+}
+
+impl Default for RuleSet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub fn solve_with_rules(input: impl std::io::BufRead, rules: &RuleSet) -> usize {
     input
         .lines()
         .filter(|line| {
             let line = line.as_ref().unwrap();
             let chars = line.as_bytes();
-            is_naughty(chars)
+            rules.is_nice(chars)
         })
         .count()
 }
 
+pub fn explain_with_rules(input: impl std::io::BufRead, rules: &RuleSet) -> String {
+    let mut nice = 0;
+    let mut lines: Vec<String> = input
+        .lines()
+        .map(|line| {
+            let line = line.unwrap();
+            let results = rules.explain(line.as_bytes());
+            let is_nice = results.iter().all(|(_, passed)| *passed);
+            if is_nice {
+                nice += 1;
+            }
+
+            let results = results
+                .iter()
+                .map(|(name, passed)| {
+                    format!("{}: {}", name, if *passed { "passed" } else { "failed" })
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            let verdict = if is_nice { "nice" } else { "naughty" };
+            format!("{}: {} ({})", line, verdict, results)
+        })
+        .collect();
+
+    lines.push(format!("Total nice = {}", nice));
+    lines.join("\n")
+}
+
 pub fn solve_a(input: impl std::io::BufRead) -> usize {
-    solve(input, |chars| {
-        has_three_vowels(chars) && has_letter_twice_in_a_row(chars) && has_no_naughty_strings(chars)
-    })
+    solve_with_rules(input, &RuleSet::parse(PART_A_RULES))
 }
 
 pub fn solve_b(input: impl std::io::BufRead) -> usize {
-    solve(input, |chars| {
-        has_pair_of_letters_twice(chars) && has_double_letter_with_a_letter_between(chars)
-    })
+    solve_with_rules(input, &RuleSet::parse(PART_B_RULES))
+}
+
+pub fn explain_a(input: impl std::io::BufRead) -> String {
+    explain_with_rules(input, &RuleSet::parse(PART_A_RULES))
+}
+
+pub fn explain_b(input: impl std::io::BufRead) -> String {
+    explain_with_rules(input, &RuleSet::parse(PART_B_RULES))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn rule(input: &str) -> Box<dyn StringRule> {
+        build_rule(&parse_expr(input).unwrap().1)
+    }
+
     #[test]
     fn check_impl() {
-        assert!(has_three_vowels(b"aei"));
-        assert!(has_three_vowels(b"xazegov"));
-        assert!(has_three_vowels(b"aeiouaeiouaeiou"));
-        assert!(!has_three_vowels(b"aci"));
+        let has_three_vowels = rule(r#"count("aeiou", 3)"#);
+        assert!(has_three_vowels.is_match(b"aei"));
+        assert!(has_three_vowels.is_match(b"xazegov"));
+        assert!(has_three_vowels.is_match(b"aeiouaeiouaeiou"));
+        assert!(!has_three_vowels.is_match(b"aci"));
 
-        assert!(has_letter_twice_in_a_row(b"xx"));
-        assert!(has_letter_twice_in_a_row(b"abcdde"));
-        assert!(has_letter_twice_in_a_row(b"aabbccdd"));
-        assert!(!has_letter_twice_in_a_row(b"abc"));
+        let has_letter_twice_in_a_row = rule(r#"pattern("xx")"#);
+        assert!(has_letter_twice_in_a_row.is_match(b"xx"));
+        assert!(has_letter_twice_in_a_row.is_match(b"abcdde"));
+        assert!(has_letter_twice_in_a_row.is_match(b"aabbccdd"));
+        assert!(!has_letter_twice_in_a_row.is_match(b"abc"));
 
-        assert!(!has_no_naughty_strings(b"ab"));
-        assert!(!has_no_naughty_strings(b"cd"));
-        assert!(has_no_naughty_strings(b"ef"));
+        let has_no_naughty_strings = rule(r#"not(any(contains("ab"), contains("cd")))"#);
+        assert!(!has_no_naughty_strings.is_match(b"ab"));
+        assert!(!has_no_naughty_strings.is_match(b"cd"));
+        assert!(has_no_naughty_strings.is_match(b"ef"));
 
-        assert!(has_double_letter_with_a_letter_between(b"xyx"));
-        assert!(has_double_letter_with_a_letter_between(b"abcdefeghi"));
-        assert!(has_double_letter_with_a_letter_between(b"aaa"));
-        assert!(!has_double_letter_with_a_letter_between(b"aab"));
+        let has_double_letter_with_a_letter_between = rule(r#"pattern("x?x")"#);
+        assert!(has_double_letter_with_a_letter_between.is_match(b"xyx"));
+        assert!(has_double_letter_with_a_letter_between.is_match(b"abcdefeghi"));
+        assert!(has_double_letter_with_a_letter_between.is_match(b"aaa"));
+        assert!(!has_double_letter_with_a_letter_between.is_match(b"aab"));
 
-        assert!(has_pair_of_letters_twice(b"xyxy"));
-        assert!(has_pair_of_letters_twice(b"aabcdefgaa"));
-        assert!(!has_pair_of_letters_twice(b"aaa"));
+        let has_pair_of_letters_twice = rule(r#"pattern("xy*xy")"#);
+        assert!(has_pair_of_letters_twice.is_match(b"xyxy"));
+        assert!(has_pair_of_letters_twice.is_match(b"aabcdefgaa"));
+        assert!(!has_pair_of_letters_twice.is_match(b"aaa"));
     }
 
     #[test]
@@ -104,4 +332,41 @@ mod tests {
         assert_eq!(0, solve_b(&b"uurcxstgmygtbstg"[..]));
         assert_eq!(0, solve_b(&b"ieodomkazucvgmuy"[..]));
     }
+
+    #[test]
+    fn check_rules() {
+        assert!(rule(r#"all()"#).is_match(b"abc"));
+        assert!(!rule(r#"any()"#).is_match(b"abc"));
+        assert!(rule(r#"all(contains("b"), not(contains("d")))"#).is_match(b"abc"));
+        assert!(rule(r#"pattern("x*y*x")"#).is_match(b"aba"));
+        assert!(rule(r#"pattern("xyz*zyx")"#).is_match(b"abccba"));
+        assert!(!rule(r#"pattern("xyz*zyx")"#).is_match(b"abcba"));
+        assert!(rule(r#"pattern("xy?yx")"#).is_match(b"zabcbaz"));
+
+        struct Shorter(usize);
+        impl StringRule for Shorter {
+            fn is_match(&self, cs: &[u8]) -> bool {
+                cs.len() < self.0
+            }
+        }
+
+        let rules = RuleSet::parse(
+            "# comments and blank lines are ignored\n\n\
+             vowels = count(\"aeiou\", 2)\n\
+             no-z = not(contains(\"z\"))\n",
+        )
+        .with_rule("short", Box::new(Shorter(5)));
+
+        assert!(rules.is_nice(b"aebc"));
+        assert_eq!(
+            vec![("vowels", true), ("no-z", false), ("short", false)],
+            rules.explain(b"aebcz")
+        );
+        assert_eq!(
+            "aebc: nice (vowels: passed, no-z: passed, short: passed)\n\
+             zzz: naughty (vowels: failed, no-z: failed, short: passed)\n\
+             Total nice = 1",
+            explain_with_rules(&b"aebc\nzzz"[..], &rules)
+        );
+    }
 }
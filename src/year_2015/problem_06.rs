@@ -1,16 +1,17 @@
 use crate::utils::parsing::{parse_decimal, parse_ws};
+use itertools::Itertools;
 use nom::{bytes::complete::tag, character::complete::char, combinator::map, ToUsize};
 
 type Coord = usize;
-type Brigthness = u8;
+pub type Brightness = u32;
 
 const GRID_SIDE: Coord = 1000;
-type Grid = Vec<Brigthness>;
+pub type Grid = Vec<Brightness>;
 
 type NomResult<'a, T> = nom::IResult<&'a str, T>;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
-enum Command {
+pub enum Command {
     On,
     Off,
     Toggle,
@@ -66,39 +67,89 @@ fn parse_instruction(input: &str) -> Instruction {
     }
 }
 
-type ChangeBrightness = fn(Brigthness) -> Brigthness;
-type ChangeBrightnessFactory = fn(Command) -> ChangeBrightness;
+pub trait LightRules {
+    fn change_brightness(&self, cmd: Command, brightness: Brightness) -> Brightness;
+}
+
+pub struct OnOffRules;
+
+impl LightRules for OnOffRules {
+    fn change_brightness(&self, cmd: Command, brightness: Brightness) -> Brightness {
+        match cmd {
+            Command::On => 1,
+            Command::Off => 0,
+            Command::Toggle => (brightness + 1) % 2,
+        }
+    }
+}
+
+pub struct DimmerRules;
+
+impl LightRules for DimmerRules {
+    fn change_brightness(&self, cmd: Command, brightness: Brightness) -> Brightness {
+        match cmd {
+            Command::On => brightness.saturating_add(1),
+            Command::Off => brightness.saturating_sub(1),
+            Command::Toggle => brightness.saturating_add(2),
+        }
+    }
+}
+
+pub struct SaturatingRules {
+    pub max: Brightness,
+}
+
+impl LightRules for SaturatingRules {
+    fn change_brightness(&self, cmd: Command, brightness: Brightness) -> Brightness {
+        DimmerRules.change_brightness(cmd, brightness).min(self.max)
+    }
+}
+
+pub struct ModularRules {
+    pub modulus: Brightness,
+}
 
-fn change_brightness_factory_a(cmd: Command) -> ChangeBrightness {
-    match cmd {
-        Command::On => |_| 1,
-        Command::Off => |_| 0,
-        Command::Toggle => |brightness| (brightness + 1) % 2,
+impl LightRules for ModularRules {
+    fn change_brightness(&self, cmd: Command, brightness: Brightness) -> Brightness {
+        let modulus = u64::from(self.modulus);
+        let step = match cmd {
+            Command::On => 1,
+            Command::Off => modulus - 1,
+            Command::Toggle => 2,
+        };
+
+        ((u64::from(brightness) + step) % modulus) as Brightness
     }
 }
 
-fn change_brightness_factory_b(cmd: Command) -> ChangeBrightness {
-    match cmd {
-        Command::On => |brightness| brightness + 1,
-        Command::Off => |brightness| if brightness != 0 { brightness - 1 } else { 0 },
-        Command::Toggle => |brightness| brightness + 2,
+pub fn make_light_rules(name: &str) -> Option<Box<dyn LightRules>> {
+    let (name, param) = match name.split_once(':') {
+        Some((name, param)) => (name, Some(param.parse::<Brightness>().ok()?)),
+        None => (name, None),
+    };
+
+    match (name, param) {
+        ("onoff", None) => Some(Box::new(OnOffRules)),
+        ("dimmer", None) => Some(Box::new(DimmerRules)),
+        ("saturating", max) => Some(Box::new(SaturatingRules {
+            max: max.unwrap_or(255),
+        })),
+        ("modular", modulus) if modulus != Some(0) => Some(Box::new(ModularRules {
+            modulus: modulus.unwrap_or(256),
+        })),
+        _ => None,
     }
 }
 
-fn get_grid_brightness(grid: &mut Grid, x: Coord, y: Coord) -> &mut Brigthness {
+fn get_grid_brightness(grid: &mut Grid, x: Coord, y: Coord) -> &mut Brightness {
     grid.get_mut(y * GRID_SIDE + x).unwrap()
 }
 
-fn apply_instruction(
-    mut grid: Grid,
-    instruction: Instruction,
-    change_brightness_factory: ChangeBrightnessFactory,
-) -> Grid {
-    let change_brightness = change_brightness_factory(instruction.cmd);
+fn apply_instruction(mut grid: Grid, instruction: Instruction, rules: &dyn LightRules) -> Grid {
     for y in instruction.top..instruction.bottom + 1 {
         for x in instruction.left..instruction.right + 1 {
             let brightness = get_grid_brightness(&mut grid, x, y);
-            *brightness = change_brightness(*brightness);
+            *brightness = rules.change_brightness(instruction.cmd, *brightness);
         }
     }
 
@@ -111,30 +162,253 @@ fn make_initial_grid() -> Grid {
     grid
 }
 
-fn solve(
-    input: impl std::io::BufRead,
-    change_brightness_factory: ChangeBrightnessFactory,
-) -> usize {
-    let instructions = input
+#[derive(Debug)]
+struct SparseGrid {
+    xs: Vec<Coord>,
+    ys: Vec<Coord>,
+    blocks: Vec<Brightness>,
+}
+
+fn make_boundaries(ranges: impl Iterator<Item = (Coord, Coord)>) -> Vec<Coord> {
+    ranges
+        .flat_map(|(from, to)| std::iter::once(from).chain(std::iter::once(to + 1)))
+        .sorted()
+        .dedup()
+        .collect()
+}
+
+fn make_sparse_grid(instructions: &[Instruction]) -> SparseGrid {
+    let xs = make_boundaries(instructions.iter().map(|i| (i.left, i.right)));
+    let ys = make_boundaries(instructions.iter().map(|i| (i.top, i.bottom)));
+
+    let blocks_count = xs.len().saturating_sub(1) * ys.len().saturating_sub(1);
+    let blocks = vec![0; blocks_count];
+
+    SparseGrid { xs, ys, blocks }
+}
+
+fn find_boundary(boundaries: &[Coord], coord: Coord) -> usize {
+    boundaries.binary_search(&coord).unwrap()
+}
+
+fn apply_instruction_sparse(
+    mut grid: SparseGrid,
+    instruction: &Instruction,
+    rules: &dyn LightRules,
+) -> SparseGrid {
+    let row_len = grid.xs.len() - 1;
+
+    let x_from = find_boundary(&grid.xs, instruction.left);
+    let x_to = find_boundary(&grid.xs, instruction.right + 1);
+    let y_from = find_boundary(&grid.ys, instruction.top);
+    let y_to = find_boundary(&grid.ys, instruction.bottom + 1);
+
+    for y in y_from..y_to {
+        for x in x_from..x_to {
+            let brightness = grid.blocks.get_mut(y * row_len + x).unwrap();
+            *brightness = rules.change_brightness(instruction.cmd, *brightness);
+        }
+    }
+
+    grid
+}
+
+fn calc_sparse_grid_brightness(grid: &SparseGrid) -> usize {
+    let widths: Vec<usize> = grid.xs.windows(2).map(|w| w[1] - w[0]).collect();
+    let heights = grid.ys.windows(2).map(|w| w[1] - w[0]);
+
+    heights
+        .zip(grid.blocks.chunks(widths.len().max(1)))
+        .map(|(height, row)| {
+            let row_brightness: usize = row
+                .iter()
+                .zip(widths.iter())
+                .map(|(brightness, width)| brightness.to_usize() * width)
+                .sum();
+            row_brightness * height
+        })
+        .sum()
+}
+
+fn parse_instructions(input: impl std::io::BufRead) -> Vec<Instruction> {
+    input
         .lines()
-        .map(|line| parse_instruction(line.unwrap().as_str()));
+        .map(|line| parse_instruction(line.unwrap().as_str()))
+        .collect()
+}
+
+static ASCII_RAMP: &[u8] = b" .:-=+*#%@";
 
-    let final_grid = instructions.fold(make_initial_grid(), |grid, instruction| {
-        apply_instruction(grid, instruction, change_brightness_factory)
+fn get_max_brightness(grid: &Grid) -> Brightness {
+    grid.iter().copied().max().unwrap_or(0)
+}
+
+fn to_grayscale(brightness: u64, max_brightness: u64, levels: u64) -> u64 {
+    if max_brightness == 0 {
+        return 0;
+    }
+
+    brightness * (levels - 1) / max_brightness
+}
+
+pub fn render_grid_pgm(grid: &Grid) -> String {
+    let max_brightness = get_max_brightness(grid);
+    let header = format!("P2\n{} {}\n255", GRID_SIDE, GRID_SIDE);
+
+    let rows = grid.chunks(GRID_SIDE).map(|row| {
+        row.iter()
+            .map(|&brightness| to_grayscale(brightness as u64, max_brightness as u64, 256))
+            .join(" ")
     });
 
-    final_grid
+    std::iter::once(header).chain(rows).join("\n")
+}
+
+pub fn render_grid_ascii(grid: &Grid, scale: std::num::NonZeroUsize) -> String {
+    let scale = scale.get();
+    let max_brightness = get_max_brightness(grid);
+    let block_starts = (0..GRID_SIDE).step_by(scale);
+
+    let render_block = |left: Coord, top: Coord| {
+        let right = (left + scale).min(GRID_SIDE);
+        let bottom = (top + scale).min(GRID_SIDE);
+
+        let block_brightness: u64 = (top..bottom)
+            .flat_map(|y| grid[y * GRID_SIDE + left..y * GRID_SIDE + right].iter())
+            .map(|&brightness| brightness as u64)
+            .sum();
+        let cells = ((right - left) * (bottom - top)) as u64;
+
+        let level = to_grayscale(
+            block_brightness,
+            max_brightness as u64 * cells,
+            ASCII_RAMP.len() as u64,
+        );
+        ASCII_RAMP[level as usize] as char
+    };
+
+    block_starts
+        .clone()
+        .map(|top| {
+            block_starts
+                .clone()
+                .map(|left| render_block(left, top))
+                .collect::<String>()
+        })
+        .join("\n")
+}
+
+fn run_instructions(instructions: Vec<Instruction>, rules: &dyn LightRules) -> Grid {
+    instructions
+        .into_iter()
+        .fold(make_initial_grid(), |grid, instruction| {
+            apply_instruction(grid, instruction, rules)
+        })
+}
+
+fn calc_grid_brightness(grid: &Grid) -> usize {
+    grid.iter().map(|brightness| brightness.to_usize()).sum()
+}
+
+fn count_lit_lights(grid: &Grid) -> usize {
+    grid.iter().filter(|&&brightness| brightness != 0).count()
+}
+
+#[derive(Debug)]
+pub struct ReplayStep {
+    pub step: usize,
+    pub lit: usize,
+    pub brightness: usize,
+    pub snapshot: Option<Grid>,
+}
+
+fn replay_instructions(
+    instructions: Vec<Instruction>,
+    rules: Box<dyn LightRules>,
+    snapshot_every: Option<std::num::NonZeroUsize>,
+) -> impl Iterator<Item = ReplayStep> {
+    instructions.into_iter().enumerate().scan(
+        make_initial_grid(),
+        move |grid, (index, instruction)| {
+            let step = index + 1;
+            *grid = apply_instruction(std::mem::take(grid), instruction, rules.as_ref());
+
+            let snapshot = snapshot_every
+                .filter(|every| step % every.get() == 0)
+                .map(|_| grid.clone());
+
+            Some(ReplayStep {
+                step,
+                lit: count_lit_lights(grid),
+                brightness: calc_grid_brightness(grid),
+                snapshot,
+            })
+        },
+    )
+}
+
+fn solve(input: impl std::io::BufRead, rules: &dyn LightRules) -> usize {
+    let final_grid = run_instructions(parse_instructions(input), rules);
+    calc_grid_brightness(&final_grid)
+}
+
+fn solve_sparse(input: impl std::io::BufRead, rules: &dyn LightRules) -> usize {
+    let instructions = parse_instructions(input);
+
+    let final_grid = instructions
         .iter()
-        .map(|brightness| brightness.to_usize())
-        .sum()
+        .fold(make_sparse_grid(&instructions), |grid, instruction| {
+            apply_instruction_sparse(grid, instruction, rules)
+        });
+
+    calc_sparse_grid_brightness(&final_grid)
 }
 
 pub fn solve_a(input: impl std::io::BufRead) -> usize {
-    solve(input, change_brightness_factory_a)
+    solve(input, &OnOffRules)
 }
 
 pub fn solve_b(input: impl std::io::BufRead) -> usize {
-    solve(input, change_brightness_factory_b)
+    solve(input, &DimmerRules)
+}
+
+pub fn solve_with_rules(input: impl std::io::BufRead, rules_name: &str) -> usize {
+    let rules = make_light_rules(rules_name).expect("Unknown light rules");
+    solve_sparse(input, rules.as_ref())
+}
+
+pub fn render_pgm(input: impl std::io::BufRead, rules_name: &str) -> String {
+    let rules = make_light_rules(rules_name).expect("Unknown light rules");
+    render_grid_pgm(&run_instructions(parse_instructions(input), rules.as_ref()))
+}
+
+pub fn render_ascii(
+    input: impl std::io::BufRead,
+    rules_name: &str,
+    scale: std::num::NonZeroUsize,
+) -> String {
+    let rules = make_light_rules(rules_name).expect("Unknown light rules");
+    render_grid_ascii(
+        &run_instructions(parse_instructions(input), rules.as_ref()),
+        scale,
+    )
+}
+
+pub fn replay(
+    input: impl std::io::BufRead,
+    rules_name: &str,
+    snapshot_every: Option<std::num::NonZeroUsize>,
+) -> impl Iterator<Item = ReplayStep> {
+    let rules = make_light_rules(rules_name).expect("Unknown light rules");
+    replay_instructions(parse_instructions(input), rules, snapshot_every)
+}
+
+pub fn solve_sparse_a(input: impl std::io::BufRead) -> usize {
+    solve_sparse(input, &OnOffRules)
+}
+
+pub fn solve_sparse_b(input: impl std::io::BufRead) -> usize {
+    solve_sparse(input, &DimmerRules)
 }
 
 #[cfg(test)]
@@ -178,4 +452,154 @@ mod tests {
         assert_eq!(1, solve_b(&b"turn on 0,0 through 0,0"[..]));
         assert_eq!(20, solve_b(&b"toggle 0,0 through 9,0"[..]));
     }
+
+    #[test]
+    fn check_sparse() {
+        let inputs: [&[u8]; 4] = [
+            b"turn on 0,0 through 1,1",
+            b"turn on 0,0 through 9,9\nturn off 4,4 through 5,5",
+            b"toggle 0,0 through 9,0\ntoggle 0,0 through 9,0",
+            b"turn on 0,0 through 999,999\ntoggle 0,0 through 999,0\nturn off 499,499 through 500,500\ntoggle 3,7 through 600,20",
+        ];
+
+        for input in inputs.iter() {
+            assert_eq!(solve_a(*input), solve_sparse_a(*input));
+            assert_eq!(solve_b(*input), solve_sparse_b(*input));
+        }
+
+        assert_eq!(0, solve_sparse_a(&b""[..]));
+        assert_eq!(
+            1_000_000_000_000 - 4,
+            solve_sparse_a(&b"turn on 0,0 through 999999,999999\nturn off 1,1 through 2,2"[..])
+        );
+        assert_eq!(
+            2_000_000_000_000,
+            solve_sparse_b(&b"toggle 0,0 through 999999,999999"[..])
+        );
+    }
+
+    #[test]
+    fn check_rules() {
+        assert_eq!(1, OnOffRules.change_brightness(Command::Toggle, 0));
+        assert_eq!(0, OnOffRules.change_brightness(Command::Toggle, 1));
+
+        assert_eq!(0, DimmerRules.change_brightness(Command::Off, 0));
+        assert_eq!(
+            Brightness::MAX,
+            DimmerRules.change_brightness(Command::Toggle, Brightness::MAX - 1)
+        );
+
+        let saturating = SaturatingRules { max: 255 };
+        assert_eq!(255, saturating.change_brightness(Command::Toggle, 254));
+        assert_eq!(254, saturating.change_brightness(Command::Off, 255));
+
+        let modular = ModularRules { modulus: 256 };
+        assert_eq!(1, modular.change_brightness(Command::Toggle, 255));
+        assert_eq!(255, modular.change_brightness(Command::Off, 0));
+
+        let modular = ModularRules {
+            modulus: Brightness::MAX,
+        };
+        assert_eq!(
+            0,
+            modular.change_brightness(Command::On, Brightness::MAX - 1)
+        );
+        assert_eq!(
+            1,
+            modular.change_brightness(Command::Toggle, Brightness::MAX - 1)
+        );
+        assert_eq!(
+            Brightness::MAX - 1,
+            modular.change_brightness(Command::Off, 0)
+        );
+        assert_eq!(
+            Brightness::MAX - 3,
+            modular.change_brightness(Command::Off, Brightness::MAX - 2)
+        );
+
+        assert!(make_light_rules("unknown").is_none());
+
+        let change = |name: &str, cmd: Command, brightness: Brightness| {
+            make_light_rules(name)
+                .unwrap()
+                .change_brightness(cmd, brightness)
+        };
+        assert_eq!(255, change("saturating", Command::Toggle, 254));
+        assert_eq!(10, change("saturating:10", Command::Toggle, 9));
+        assert_eq!(0, change("modular", Command::On, 255));
+        assert_eq!(1, change("modular:3", Command::Toggle, 2));
+        assert_eq!(6, change("modular:7", Command::Off, 0));
+        assert_eq!(
+            2999999998,
+            change("modular:3000000000", Command::Off, 2999999999)
+        );
+        assert_eq!(1, change("modular:3000000000", Command::Toggle, 2999999999));
+        assert!(make_light_rules("modular:0").is_none());
+        assert!(make_light_rules("saturating:x").is_none());
+        assert!(make_light_rules("dimmer:5").is_none());
+
+        let toggles = "toggle 0,0 through 0,0\n".repeat(200);
+        assert_eq!(400, solve_b(toggles.as_bytes()));
+        assert_eq!(400, solve_with_rules(toggles.as_bytes(), "dimmer"));
+        assert_eq!(255, solve_with_rules(toggles.as_bytes(), "saturating"));
+        assert_eq!(400 % 256, solve_with_rules(toggles.as_bytes(), "modular"));
+        assert_eq!(0, solve_with_rules(toggles.as_bytes(), "onoff"));
+    }
+
+    #[test]
+    fn check_rendering() {
+        let input = b"turn on 0,0 through 499,999\ntoggle 0,0 through 999,99";
+        let scale = |scale| std::num::NonZeroUsize::new(scale).unwrap();
+
+        let ascii = render_ascii(&input[..], "onoff", scale(100));
+        assert_eq!(
+            "     @@@@@\n@@@@@     \n@@@@@     \n@@@@@     \n@@@@@     \n@@@@@     \n@@@@@     \n@@@@@     \n@@@@@     \n@@@@@     ",
+            ascii
+        );
+
+        assert_eq!("=.\n- ", render_ascii(&input[..], "dimmer", scale(500)));
+
+        let pgm = render_pgm(&input[..], "dimmer");
+        let mut lines = pgm.lines();
+        assert_eq!(Some("P2"), lines.next());
+        assert_eq!(Some("1000 1000"), lines.next());
+        assert_eq!(Some("255"), lines.next());
+
+        let first_row: Vec<&str> = lines.next().unwrap().split(' ').collect();
+        assert_eq!(GRID_SIDE, first_row.len());
+        assert_eq!("255", first_row[0]);
+        assert_eq!("170", first_row[999]);
+        assert_eq!(GRID_SIDE, pgm.lines().count() - 3);
+
+        assert_eq!(" ", render_ascii(&b""[..], "onoff", scale(1000)));
+    }
+
+    #[test]
+    fn check_replay() {
+        let input = b"turn on 0,0 through 9,9\ntoggle 0,0 through 0,9\nturn off 5,5 through 9,9";
+
+        let steps: Vec<_> = replay(&input[..], "onoff", None)
+            .map(|step| (step.step, step.lit, step.brightness))
+            .collect();
+        assert_eq!(vec![(1, 100, 100), (2, 90, 90), (3, 65, 65)], steps);
+
+        let steps: Vec<_> = replay(&input[..], "dimmer", None)
+            .map(|step| (step.step, step.lit, step.brightness))
+            .collect();
+        assert_eq!(vec![(1, 100, 100), (2, 100, 120), (3, 75, 95)], steps);
+
+        let snapshots: Vec<_> = replay(&input[..], "onoff", std::num::NonZeroUsize::new(2))
+            .map(|step| step.snapshot.map(|grid| calc_grid_brightness(&grid)))
+            .collect();
+        assert_eq!(vec![None, Some(90), None], snapshots);
+
+        let last_step = replay(&input[..], "dimmer", std::num::NonZeroUsize::new(1))
+            .last()
+            .unwrap();
+        assert_eq!(
+            Some(95),
+            last_step.snapshot.map(|grid| calc_grid_brightness(&grid))
+        );
+        assert_eq!(solve_b(&input[..]), last_step.brightness);
+    }
 }
@@ -1,20 +1,32 @@
 use crate::utils::parsing::{parse_decimal, parse_str_alpha, parse_ws};
+use itertools::Itertools;
 use nom::{branch::alt, bytes::complete::tag, combinator::map};
 
 type Signal = u16;
 type WireId = String;
-type UnaryOp = fn(Signal) -> Signal;
-type BinaryOp = fn(Signal, Signal) -> Signal;
 
 type NomResult<'a, T> = nom::IResult<&'a str, T>;
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum UnaryOp {
+    Not,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum BinaryOp {
+    And,
+    Or,
+    LShift,
+    RShift,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
 enum Value {
     Signal(Signal),
     Wire(WireId),
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 enum Source {
     Value(Value),
     UnaryGate(UnaryOp, Value),
@@ -29,6 +41,22 @@ struct Instruction {
 
 type WireMap = std::collections::HashMap<WireId, Source>;
 type SignalCache = std::collections::HashMap<WireId, Signal>;
+type WireSet = std::collections::HashSet<WireId>;
+
+fn apply_unary_op(op: UnaryOp, a: Signal) -> Signal {
+    match op {
+        UnaryOp::Not => !a,
+    }
+}
+
+fn apply_binary_op(op: BinaryOp, a: Signal, b: Signal) -> Option<Signal> {
+    match op {
+        BinaryOp::And => Some(a & b),
+        BinaryOp::Or => Some(a | b),
+        BinaryOp::LShift => a.checked_shl(b.into()),
+        BinaryOp::RShift => a.checked_shr(b.into()),
+    }
+}
 
 fn get_value_signal(
     value: &Value,
@@ -41,18 +69,27 @@ fn get_value_signal(
     }
 }
 
+fn calc_source_signal_with(
+    source: &Source,
+    mut value_signal: impl FnMut(&Value) -> Option<Signal>,
+) -> Option<Signal> {
+    match source {
+        Source::Value(value) => value_signal(value),
+        Source::UnaryGate(op, arg) => value_signal(arg).map(|a| apply_unary_op(*op, a)),
+        Source::BinaryGate(op, arg1, arg2) => value_signal(arg1)
+            .zip(value_signal(arg2))
+            .and_then(|(a, b)| apply_binary_op(*op, a, b)),
+    }
+}
+
 fn calc_source_signal(
     source: &Source,
     wiremap: &WireMap,
     signal_cache: &mut SignalCache,
 ) -> Option<Signal> {
-    match source {
-        Source::Value(value) => get_value_signal(value, wiremap, signal_cache),
-        Source::UnaryGate(op, arg) => get_value_signal(arg, wiremap, signal_cache).map(op),
-        Source::BinaryGate(op, arg1, arg2) => get_value_signal(arg1, wiremap, signal_cache)
-            .zip(get_value_signal(arg2, wiremap, signal_cache))
-            .map(|(a, b)| op(a, b)),
-    }
+    calc_source_signal_with(source, |value| {
+        get_value_signal(value, wiremap, signal_cache)
+    })
 }
 
 fn get_wire_signal(
@@ -101,27 +138,14 @@ fn parse_unary_gate(input: &str) -> NomResult<Source> {
     let (input, _) = tag("NOT")(input)?;
     let (input, arg1) = parse_value(input)?;
 
-    Ok((input, Source::UnaryGate(|param1| !param1, arg1)))
+    Ok((input, Source::UnaryGate(UnaryOp::Not, arg1)))
 }
 
 fn parse_binary_gate(input: &str) -> NomResult<Source> {
-    fn lshift_op(a: Signal, b: Signal) -> Signal {
-        a << b
-    }
-    fn rshift_op(a: Signal, b: Signal) -> Signal {
-        a >> b
-    }
-    fn and_op(a: Signal, b: Signal) -> Signal {
-        a & b
-    }
-    fn or_op(a: Signal, b: Signal) -> Signal {
-        a | b
-    }
-
-    let parse_lshift = map(tag("LSHIFT"), |_| lshift_op as BinaryOp);
-    let parse_rshift = map(tag("RSHIFT"), |_| rshift_op as BinaryOp);
-    let parse_and = map(tag("AND"), |_| and_op as BinaryOp);
-    let parse_or = map(tag("OR"), |_| or_op as BinaryOp);
+    let parse_lshift = map(tag("LSHIFT"), |_| BinaryOp::LShift);
+    let parse_rshift = map(tag("RSHIFT"), |_| BinaryOp::RShift);
+    let parse_and = map(tag("AND"), |_| BinaryOp::And);
+    let parse_or = map(tag("OR"), |_| BinaryOp::Or);
 
     let (input, arg1) = parse_value(input)?;
     let (input, op) = alt((parse_lshift, parse_rshift, parse_and, parse_or))(input)?;
@@ -150,19 +174,211 @@ fn parse_instruction(input: &str) -> Instruction {
     }
 }
 
-pub fn solve_a(input: impl std::io::BufRead) -> Signal {
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Value::Signal(signal) => write!(f, "{}", signal),
+            Value::Wire(wire) => write!(f, "{}", wire),
+        }
+    }
+}
+
+impl std::fmt::Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Source::Value(value) => write!(f, "{}", value),
+            Source::UnaryGate(UnaryOp::Not, arg) => write!(f, "NOT {}", arg),
+            Source::BinaryGate(op, arg1, arg2) => {
+                let op_name = match op {
+                    BinaryOp::And => "AND",
+                    BinaryOp::Or => "OR",
+                    BinaryOp::LShift => "LSHIFT",
+                    BinaryOp::RShift => "RSHIFT",
+                };
+                write!(f, "{} {} {}", arg1, op_name, arg2)
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct ConstantFolder {
+    signals: std::collections::HashMap<WireId, Option<Signal>>,
+    visiting: WireSet,
+}
+
+impl ConstantFolder {
+    fn wire_signal(&mut self, wire: &WireId, wiremap: &WireMap) -> Option<Signal> {
+        if let Some(&signal) = self.signals.get(wire) {
+            return signal;
+        }
+
+        let source = wiremap.get(wire)?;
+        if !self.visiting.insert(wire.clone()) {
+            return None;
+        }
+
+        let signal = calc_source_signal_with(source, |value| self.value_signal(value, wiremap));
+        self.visiting.remove(wire);
+        self.signals.insert(wire.clone(), signal);
+        signal
+    }
+
+    fn value_signal(&mut self, value: &Value, wiremap: &WireMap) -> Option<Signal> {
+        match value {
+            Value::Signal(signal) => Some(*signal),
+            Value::Wire(wire) => self.wire_signal(wire, wiremap),
+        }
+    }
+
+    fn fold_value(&mut self, value: &Value, wiremap: &WireMap) -> Value {
+        match self.value_signal(value, wiremap) {
+            Some(signal) => Value::Signal(signal),
+            None => value.clone(),
+        }
+    }
+
+    fn fold_source(&mut self, source: &Source, wiremap: &WireMap) -> Source {
+        if let Some(signal) =
+            calc_source_signal_with(source, |value| self.value_signal(value, wiremap))
+        {
+            return Source::Value(Value::Signal(signal));
+        }
+
+        match source {
+            Source::Value(value) => Source::Value(self.fold_value(value, wiremap)),
+            Source::UnaryGate(op, arg) => Source::UnaryGate(*op, self.fold_value(arg, wiremap)),
+            Source::BinaryGate(op, arg1, arg2) => Source::BinaryGate(
+                *op,
+                self.fold_value(arg1, wiremap),
+                self.fold_value(arg2, wiremap),
+            ),
+        }
+    }
+}
+
+fn fold_constants(wiremap: &WireMap) -> WireMap {
+    let mut folder = ConstantFolder::default();
+    wiremap
+        .iter()
+        .map(|(wire, source)| (wire.clone(), folder.fold_source(source, wiremap)))
+        .collect()
+}
+
+fn resolve_alias<'a>(mut wire: &'a WireId, wiremap: &'a WireMap) -> &'a WireId {
+    // bounded by the number of wires so that a cyclic alias chain can't loop forever
+    for _ in 0..wiremap.len() {
+        match wiremap.get(wire) {
+            Some(Source::Value(Value::Wire(next))) => wire = next,
+            _ => break,
+        }
+    }
+
+    wire
+}
+
+fn collapse_aliases(wiremap: &WireMap) -> WireMap {
+    let resolve_value = |value: &Value| match value {
+        Value::Wire(wire) => Value::Wire(resolve_alias(wire, wiremap).clone()),
+        signal => signal.clone(),
+    };
+
+    wiremap
+        .iter()
+        .map(|(wire, source)| {
+            let source = match source {
+                Source::Value(value) => Source::Value(resolve_value(value)),
+                Source::UnaryGate(op, arg) => Source::UnaryGate(*op, resolve_value(arg)),
+                Source::BinaryGate(op, arg1, arg2) => {
+                    Source::BinaryGate(*op, resolve_value(arg1), resolve_value(arg2))
+                }
+            };
+            (wire.clone(), source)
+        })
+        .collect()
+}
+
+fn source_wires(source: &Source) -> impl Iterator<Item = &WireId> {
+    let (arg1, arg2) = match source {
+        Source::Value(value) | Source::UnaryGate(_, value) => (value, None),
+        Source::BinaryGate(_, arg1, arg2) => (arg1, Some(arg2)),
+    };
+
+    std::iter::once(arg1)
+        .chain(arg2)
+        .filter_map(|value| match value {
+            Value::Wire(wire) => Some(wire),
+            Value::Signal(_) => None,
+        })
+}
+
+fn remove_dead_wires(mut wiremap: WireMap, output: &WireId) -> WireMap {
+    let mut alive = WireSet::new();
+    let mut pending = vec![output];
+
+    while let Some(wire) = pending.pop() {
+        if let Some(source) = wiremap.get(wire) {
+            if alive.insert(wire.clone()) {
+                pending.extend(source_wires(source));
+            }
+        }
+    }
+
+    wiremap.retain(|wire, _| alive.contains(wire));
+    wiremap
+}
+
+fn simplify_wiremap(wiremap: &WireMap, output: &WireId) -> WireMap {
+    let reachable = remove_dead_wires(wiremap.clone(), output);
+    let folded = fold_constants(&reachable);
+    let collapsed = collapse_aliases(&folded);
+    remove_dead_wires(collapsed, output)
+}
+
+fn format_wiremap(wiremap: &WireMap) -> String {
+    fn format_wire(
+        wire: &WireId,
+        wiremap: &WireMap,
+        visited: &mut WireSet,
+        lines: &mut Vec<String>,
+    ) {
+        if let Some(source) = wiremap.get(wire) {
+            if visited.insert(wire.clone()) {
+                for input_wire in source_wires(source) {
+                    format_wire(input_wire, wiremap, visited, lines);
+                }
+                lines.push(format!("{} -> {}", source, wire));
+            }
+        }
+    }
+
+    let mut visited = WireSet::new();
+    let mut lines = Vec::new();
+    for wire in wiremap.keys().sorted() {
+        format_wire(wire, wiremap, &mut visited, &mut lines);
+    }
+
+    lines.join("\n")
+}
+
+pub fn simplify_netlist(input: impl std::io::BufRead, output: &str) -> String {
     let wiremap = parse_wiremap(input);
-    get_wire_signal(&String::from("a"), &wiremap, &mut SignalCache::new()).unwrap()
+    format_wiremap(&simplify_wiremap(&wiremap, &output.to_string()))
 }
 
-pub fn solve_b(input: impl std::io::BufRead) -> Signal {
+pub fn solve_a(input: impl std::io::BufRead) -> Option<Signal> {
     let wiremap = parse_wiremap(input);
-    let signal_a = get_wire_signal(&String::from("a"), &wiremap, &mut SignalCache::new()).unwrap();
+    get_wire_signal(&String::from("a"), &wiremap, &mut SignalCache::new())
+}
+
+pub fn solve_b(input: impl std::io::BufRead) -> Option<Signal> {
+    let wiremap = parse_wiremap(input);
+    let signal_a = get_wire_signal(&String::from("a"), &wiremap, &mut SignalCache::new())?;
 
     let mut overriden_signal_cache = SignalCache::new();
     overriden_signal_cache.insert(String::from("b"), signal_a);
 
-    get_wire_signal(&String::from("a"), &wiremap, &mut overriden_signal_cache).unwrap()
+    get_wire_signal(&String::from("a"), &wiremap, &mut overriden_signal_cache)
 }
 
 #[cfg(test)]
@@ -250,4 +466,69 @@ mod tests {
             get_wire_signal(&String::from("nonexisting"), &wiremap, &mut signal_cache)
         );
     }
+
+    #[test]
+    fn check_no_solution() {
+        assert_eq!(Some(123), solve_a(&b"123 -> a"[..]));
+        assert_eq!(None, solve_a(&b"123 -> b"[..]));
+        assert_eq!(None, solve_b(&b"123 -> b"[..]));
+        assert_eq!(None, solve_b(&b"c -> a"[..]));
+        assert_eq!(None, solve_a(&b"1 LSHIFT 20 -> a"[..]));
+        assert_eq!(None, solve_a(&b"65535 RSHIFT 16 -> a"[..]));
+    }
+
+    #[test]
+    fn check_simplification() {
+        let input =
+            b"123 -> x\nx -> y\ny AND z -> d\nNOT d -> a\n1 OR 2 -> e\ne -> f\nf LSHIFT k -> g";
+
+        assert_eq!(
+            "123 AND z -> d\nNOT d -> a",
+            simplify_netlist(&input[..], "a")
+        );
+        assert_eq!("3 LSHIFT k -> g", simplify_netlist(&input[..], "g"));
+        assert_eq!("123 -> y", simplify_netlist(&input[..], "y"));
+        assert_eq!("", simplify_netlist(&input[..], "nonexisting"));
+
+        assert_eq!(
+            "5 -> a",
+            simplify_netlist(&b"5 -> a\n1 LSHIFT 20 -> dead"[..], "a")
+        );
+        assert_eq!(
+            "1 LSHIFT 20 -> a",
+            simplify_netlist(&b"1 LSHIFT 20 -> a"[..], "a")
+        );
+        assert_eq!(
+            "32768 -> a",
+            simplify_netlist(&b"1 LSHIFT 15 -> a"[..], "a")
+        );
+        assert_eq!(
+            "y -> y\ny AND 1 -> a",
+            simplify_netlist(&b"x -> y\ny -> x\nx AND 1 -> a"[..], "a")
+        );
+
+        let input = b"123 -> x\n456 -> y\nx AND y -> d\nd OR x -> e\nNOT e -> a";
+        assert_eq!("65412 -> a", simplify_netlist(&input[..], "a"));
+
+        let input = b"b -> x\nx AND 7 -> y\ny LSHIFT 1 -> z\nz OR x -> a";
+        let simplified = simplify_netlist(&input[..], "a");
+        assert_eq!("b AND 7 -> y\ny LSHIFT 1 -> z\nz OR b -> a", simplified);
+
+        let wiremap = parse_wiremap(&input[..]);
+        let simplified_wiremap = parse_wiremap(simplified.as_bytes());
+        for b in [0, 1, 5, 1234, 65535].iter() {
+            let mut signal_cache = SignalCache::new();
+            signal_cache.insert(String::from("b"), *b);
+            let mut simplified_signal_cache = signal_cache.clone();
+
+            assert_eq!(
+                get_wire_signal(&String::from("a"), &wiremap, &mut signal_cache),
+                get_wire_signal(
+                    &String::from("a"),
+                    &simplified_wiremap,
+                    &mut simplified_signal_cache
+                )
+            );
+        }
+    }
 }
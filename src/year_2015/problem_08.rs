@@ -1,43 +1,788 @@
-fn count_str_memory_len(cs: &[u8]) -> usize {
-    fn count_str_memory_len_impl(cs: &[u8], acc: usize) -> usize {
-        match cs {
-            [] => acc,
-            [b'\\', c, rest @ ..] if *c == b'\\' || *c == b'"' => {
-                count_str_memory_len_impl(rest, acc + 1)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LiteralError {
+    MissingOpeningQuote,
+    Unterminated { position: usize },
+    TrailingCharacters { position: usize },
+    InvalidEscape { position: usize, escape: u8 },
+    InvalidHexEscape { position: usize },
+    InvalidOctalEscape { position: usize },
+    InvalidUnicodeEscape { position: usize },
+}
+
+impl std::fmt::Display for LiteralError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            LiteralError::MissingOpeningQuote => write!(f, "Missing opening quote at position 0"),
+            LiteralError::Unterminated { position } => {
+                write!(f, "Unterminated literal at position {}", position)
+            }
+            LiteralError::TrailingCharacters { position } => {
+                write!(
+                    f,
+                    "Unexpected characters after literal at position {}",
+                    position
+                )
+            }
+            LiteralError::InvalidEscape { position, escape } => write!(
+                f,
+                "Invalid escape '\\{}' at position {}",
+                escape.escape_ascii(),
+                position
+            ),
+            LiteralError::InvalidHexEscape { position } => {
+                write!(f, "Invalid hex escape at position {}", position)
+            }
+            LiteralError::InvalidOctalEscape { position } => {
+                write!(f, "Invalid octal escape at position {}", position)
+            }
+            LiteralError::InvalidUnicodeEscape { position } => {
+                write!(f, "Invalid unicode escape at position {}", position)
+            }
+        }
+    }
+}
+
+impl LiteralError {
+    fn position(&self) -> Option<usize> {
+        match *self {
+            LiteralError::MissingOpeningQuote => None,
+            LiteralError::Unterminated { position }
+            | LiteralError::TrailingCharacters { position }
+            | LiteralError::InvalidEscape { position, .. }
+            | LiteralError::InvalidHexEscape { position }
+            | LiteralError::InvalidOctalEscape { position }
+            | LiteralError::InvalidUnicodeEscape { position } => Some(position),
+        }
+    }
+
+    fn map_position(self, f: impl FnOnce(usize) -> usize) -> LiteralError {
+        match self {
+            LiteralError::MissingOpeningQuote => LiteralError::MissingOpeningQuote,
+            LiteralError::Unterminated { position } => LiteralError::Unterminated {
+                position: f(position),
+            },
+            LiteralError::TrailingCharacters { position } => LiteralError::TrailingCharacters {
+                position: f(position),
+            },
+            LiteralError::InvalidEscape { position, escape } => LiteralError::InvalidEscape {
+                position: f(position),
+                escape,
+            },
+            LiteralError::InvalidHexEscape { position } => LiteralError::InvalidHexEscape {
+                position: f(position),
+            },
+            LiteralError::InvalidOctalEscape { position } => LiteralError::InvalidOctalEscape {
+                position: f(position),
+            },
+            LiteralError::InvalidUnicodeEscape { position } => LiteralError::InvalidUnicodeEscape {
+                position: f(position),
+            },
+        }
+    }
+
+    fn shifted(self, offset: usize) -> LiteralError {
+        self.map_position(|position| position + offset)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedLiteral {
+    pub bytes: Vec<u8>,
+    pub code_len: usize,
+}
+
+impl DecodedLiteral {
+    pub fn memory_len(&self) -> usize {
+        self.bytes.len()
+    }
+}
+
+pub trait LiteralDialect {
+    fn opening(&self) -> &'static [u8] {
+        b"\""
+    }
+
+    fn decode_escape(
+        &self,
+        cs: &[u8],
+        position: usize,
+        bytes: &mut Vec<u8>,
+    ) -> Result<usize, LiteralError>;
+
+    fn encode(&self, bytes: &[u8]) -> Vec<u8>;
+
+    // Literals that don't start with `opening`, e.g. raw strings.
+    fn decode_prefixed(&self, _cs: &[u8]) -> Option<Result<DecodedLiteral, LiteralError>> {
+        None
+    }
+
+    // Length of a comment or other token that can't contain a literal.
+    fn skip_len(&self, _cs: &[u8]) -> Option<usize> {
+        None
+    }
+}
+
+pub struct PuzzleDialect;
+pub struct RustDialect;
+pub struct CDialect;
+pub struct JsonDialect;
+pub struct PythonRawDialect;
+struct RustByteDialect;
+
+pub fn make_dialect(name: &str) -> Option<Box<dyn LiteralDialect>> {
+    match name {
+        "puzzle" => Some(Box::new(PuzzleDialect)),
+        "rust" => Some(Box::new(RustDialect)),
+        "c" => Some(Box::new(CDialect)),
+        "json" => Some(Box::new(JsonDialect)),
+        "python-raw" => Some(Box::new(PythonRawDialect)),
+        _ => None,
+    }
+}
+
+const PUZZLE_ESCAPES: &[(u8, u8)] = &[(b'\\', b'\\'), (b'"', b'"')];
+
+const RUST_ESCAPES: &[(u8, u8)] = &[
+    (b'\\', b'\\'),
+    (b'"', b'"'),
+    (b'\'', b'\''),
+    (b'n', b'\n'),
+    (b'r', b'\r'),
+    (b't', b'\t'),
+    (b'0', 0),
+];
+
+const C_ESCAPES: &[(u8, u8)] = &[
+    (b'\\', b'\\'),
+    (b'"', b'"'),
+    (b'\'', b'\''),
+    (b'?', b'?'),
+    (b'a', 0x07),
+    (b'b', 0x08),
+    (b'f', 0x0c),
+    (b'n', b'\n'),
+    (b'r', b'\r'),
+    (b't', b'\t'),
+    (b'v', 0x0b),
+];
+
+const JSON_ESCAPES: &[(u8, u8)] = &[
+    (b'\\', b'\\'),
+    (b'"', b'"'),
+    (b'/', b'/'),
+    (b'b', 0x08),
+    (b'f', 0x0c),
+    (b'n', b'\n'),
+    (b'r', b'\r'),
+    (b't', b'\t'),
+];
+
+fn simple_escape(escapes: &[(u8, u8)], escape: u8) -> Option<u8> {
+    escapes
+        .iter()
+        .find(|&&(name, _)| name == escape)
+        .map(|&(_, c)| c)
+}
+
+fn escape_at(cs: &[u8], position: usize) -> Result<u8, LiteralError> {
+    cs.get(position + 1)
+        .copied()
+        .ok_or(LiteralError::Unterminated { position: cs.len() })
+}
+
+fn hex_number(digits: Option<&[u8]>) -> Option<u32> {
+    digits
+        .filter(|digits| !digits.is_empty())?
+        .iter()
+        .try_fold(0u32, |n, &c| {
+            let digit = (c as char).to_digit(16)?;
+            n.checked_mul(16)?.checked_add(digit)
+        })
+}
+
+fn push_char(bytes: &mut Vec<u8>, c: char) {
+    let mut buffer = [0; 4];
+    bytes.extend_from_slice(c.encode_utf8(&mut buffer).as_bytes());
+}
+
+fn encode_with(opening: &[u8], bytes: &[u8], escape: impl Fn(u8) -> Option<String>) -> Vec<u8> {
+    let mut encoded = Vec::with_capacity(bytes.len() + opening.len() + 1);
+    encoded.extend_from_slice(opening);
+    for &c in bytes {
+        match escape(c) {
+            Some(escaped) => encoded.extend_from_slice(escaped.as_bytes()),
+            None => encoded.push(c),
+        }
+    }
+    encoded.push(b'"');
+
+    encoded
+}
+
+fn line_comment_len(cs: &[u8]) -> usize {
+    cs.iter().position(|&c| c == b'\n').unwrap_or(cs.len())
+}
+
+fn block_comment_len(cs: &[u8], nested: bool) -> usize {
+    let mut depth = 0;
+    let mut i = 0;
+    while i + 1 < cs.len() {
+        match &cs[i..i + 2] {
+            b"/*" if nested || depth == 0 => {
+                depth += 1;
+                i += 2;
             }
-            [b'\\', b'x', _, _, rest @ ..] => count_str_memory_len_impl(rest, acc + 1),
-            [_, rest @ ..] => count_str_memory_len_impl(rest, 1 + acc),
+            b"*/" => {
+                depth -= 1;
+                i += 2;
+                if depth == 0 {
+                    return i;
+                }
+            }
+            _ => i += 1,
+        }
+    }
+
+    cs.len()
+}
+
+fn c_like_skip_len(cs: &[u8], nested_comments: bool) -> Option<usize> {
+    match cs {
+        [b'/', b'/', ..] => Some(line_comment_len(cs)),
+        [b'/', b'*', ..] => Some(block_comment_len(cs, nested_comments)),
+        [b'\'', ..] => Some(char_literal_len(cs)),
+        _ => None,
+    }
+}
+
+// A quote that doesn't start a character literal is a lifetime or a label.
+fn char_literal_len(cs: &[u8]) -> usize {
+    let len = match cs.get(1) {
+        Some(b'\\') => cs
+            .iter()
+            .skip(3)
+            .position(|&c| c == b'\'')
+            .map(|len| len + 4),
+        Some(&c) => {
+            let width = match c {
+                0x00..=0x7f => 1,
+                0xc0..=0xdf => 2,
+                0xe0..=0xef => 3,
+                _ => 4,
+            };
+            Some(width + 2).filter(|&len| cs.get(len - 1) == Some(&b'\''))
         }
+        None => None,
     };
 
-    cs.len() - (count_str_memory_len_impl(cs, 0) - 2)
+    len.unwrap_or(1)
+}
+
+fn quoted_len(cs: &[u8], quote: &[u8]) -> usize {
+    let mut i = quote.len();
+    while i < cs.len() {
+        if cs[i] == b'\\' {
+            i += 2;
+        } else if cs[i..].starts_with(quote) {
+            return i + quote.len();
+        } else {
+            i += 1;
+        }
+    }
+
+    cs.len()
+}
+
+fn decode_rust_raw(cs: &[u8]) -> Option<Result<DecodedLiteral, LiteralError>> {
+    let prefix_len = if cs.starts_with(b"br") { 2 } else { 1 };
+    let hashes = cs[prefix_len..].iter().take_while(|&&c| c == b'#').count();
+    if cs.get(prefix_len + hashes) != Some(&b'"') {
+        return None;
+    }
+
+    let start = prefix_len + hashes + 1;
+    let closing = [&b"\""[..], &b"#".repeat(hashes)].concat();
+    let literal = cs[start..]
+        .windows(closing.len())
+        .position(|w| w == closing.as_slice())
+        .map(|len| DecodedLiteral {
+            bytes: cs[start..start + len].to_vec(),
+            code_len: start + len + closing.len(),
+        })
+        .ok_or(LiteralError::Unterminated { position: cs.len() });
+
+    Some(literal)
+}
+
+fn decode_rust_escape(
+    cs: &[u8],
+    position: usize,
+    bytes: &mut Vec<u8>,
+    max_hex: u32,
+    unicode: bool,
+) -> Result<usize, LiteralError> {
+    let escape = escape_at(cs, position)?;
+    if let Some(c) = simple_escape(RUST_ESCAPES, escape) {
+        bytes.push(c);
+        return Ok(position + 2);
+    }
+    match escape {
+        b'\n' | b'\r' => Ok(cs[position + 1..]
+            .iter()
+            .position(|c| !matches!(c, b' ' | b'\t' | b'\n' | b'\r'))
+            .map_or(cs.len(), |len| position + 1 + len)),
+        b'x' => {
+            let value = hex_number(cs.get(position + 2..position + 4))
+                .filter(|&value| value <= max_hex)
+                .ok_or(LiteralError::InvalidHexEscape { position })?;
+            bytes.push(value as u8);
+            Ok(position + 4)
+        }
+        b'u' if unicode => {
+            let error = LiteralError::InvalidUnicodeEscape { position };
+            if cs.get(position + 2) != Some(&b'{') {
+                return Err(error);
+            }
+            let end = cs[position + 3..]
+                .iter()
+                .position(|&c| c == b'}')
+                .map(|len| position + 3 + len)
+                .filter(|end| end - position - 3 <= 6)
+                .ok_or_else(|| error.clone())?;
+            let c = hex_number(cs.get(position + 3..end))
+                .and_then(std::char::from_u32)
+                .ok_or(error)?;
+            push_char(bytes, c);
+            Ok(end + 1)
+        }
+        escape => Err(LiteralError::InvalidEscape { position, escape }),
+    }
+}
+
+fn encode_rust(opening: &[u8], bytes: &[u8], escape_non_ascii: bool) -> Vec<u8> {
+    encode_with(opening, bytes, |c| match c {
+        b'\\' | b'"' => Some(format!("\\{}", c as char)),
+        b'\n' => Some(String::from("\\n")),
+        b'\r' => Some(String::from("\\r")),
+        b'\t' => Some(String::from("\\t")),
+        0 => Some(String::from("\\0")),
+        0x01..=0x1f | 0x7f => Some(format!("\\x{:02x}", c)),
+        0x80..=0xff if escape_non_ascii => Some(format!("\\x{:02x}", c)),
+        _ => None,
+    })
+}
+
+impl LiteralDialect for PuzzleDialect {
+    fn decode_escape(
+        &self,
+        cs: &[u8],
+        position: usize,
+        bytes: &mut Vec<u8>,
+    ) -> Result<usize, LiteralError> {
+        let escape = escape_at(cs, position)?;
+        if let Some(c) = simple_escape(PUZZLE_ESCAPES, escape) {
+            bytes.push(c);
+            return Ok(position + 2);
+        }
+        match escape {
+            b'x' => {
+                let value = hex_number(cs.get(position + 2..position + 4))
+                    .ok_or(LiteralError::InvalidHexEscape { position })?;
+                bytes.push(value as u8);
+                Ok(position + 4)
+            }
+            escape => Err(LiteralError::InvalidEscape { position, escape }),
+        }
+    }
+
+    fn encode(&self, bytes: &[u8]) -> Vec<u8> {
+        encode_literal(bytes)
+    }
 }
 
-fn count_str_code_len(cs: &[u8]) -> usize {
-    fn count_str_code_len_impl(cs: &[u8], acc: usize) -> usize {
+impl LiteralDialect for RustDialect {
+    fn decode_escape(
+        &self,
+        cs: &[u8],
+        position: usize,
+        bytes: &mut Vec<u8>,
+    ) -> Result<usize, LiteralError> {
+        decode_rust_escape(cs, position, bytes, 0x7f, true)
+    }
+
+    fn encode(&self, bytes: &[u8]) -> Vec<u8> {
+        encode_rust(self.opening(), bytes, false)
+    }
+
+    fn decode_prefixed(&self, cs: &[u8]) -> Option<Result<DecodedLiteral, LiteralError>> {
         match cs {
-            [] => acc,
-            [c, rest @ ..] if *c == b'\\' || *c == b'"' => count_str_code_len_impl(rest, acc + 2),
-            [_, rest @ ..] => count_str_code_len_impl(rest, acc + 1),
+            [b'b', b'"', ..] => Some(decode_prefix(cs, &RustByteDialect)),
+            [b'r', ..] | [b'b', b'r', ..] => decode_rust_raw(cs),
+            _ => None,
         }
     }
 
-    2 + count_str_code_len_impl(cs, 0) - cs.len()
+    fn skip_len(&self, cs: &[u8]) -> Option<usize> {
+        c_like_skip_len(cs, true)
+    }
+}
+
+impl LiteralDialect for RustByteDialect {
+    fn opening(&self) -> &'static [u8] {
+        b"b\""
+    }
+
+    fn decode_escape(
+        &self,
+        cs: &[u8],
+        position: usize,
+        bytes: &mut Vec<u8>,
+    ) -> Result<usize, LiteralError> {
+        decode_rust_escape(cs, position, bytes, 0xff, false)
+    }
+
+    fn encode(&self, bytes: &[u8]) -> Vec<u8> {
+        encode_rust(self.opening(), bytes, true)
+    }
 }
 
-fn solve(input: impl std::io::BufRead, count_len: fn(&[u8]) -> usize) -> usize {
+impl LiteralDialect for CDialect {
+    fn decode_escape(
+        &self,
+        cs: &[u8],
+        position: usize,
+        bytes: &mut Vec<u8>,
+    ) -> Result<usize, LiteralError> {
+        let escape = escape_at(cs, position)?;
+        if let Some(c) = simple_escape(C_ESCAPES, escape) {
+            bytes.push(c);
+            return Ok(position + 2);
+        }
+        match escape {
+            b'\n' => Ok(position + 2),
+            b'\r' if cs.get(position + 2) == Some(&b'\n') => Ok(position + 3),
+            b'0'..=b'7' => {
+                let digits = cs[position + 1..]
+                    .iter()
+                    .take(3)
+                    .take_while(|c| (b'0'..=b'7').contains(c))
+                    .count();
+                let value = cs[position + 1..position + 1 + digits]
+                    .iter()
+                    .fold(0u32, |n, &c| n * 8 + (c - b'0') as u32);
+                if value > 0xff {
+                    return Err(LiteralError::InvalidOctalEscape { position });
+                }
+                bytes.push(value as u8);
+                Ok(position + 1 + digits)
+            }
+            b'x' => {
+                let digits = cs[position + 2..]
+                    .iter()
+                    .take_while(|c| c.is_ascii_hexdigit())
+                    .count();
+                let value = hex_number(cs.get(position + 2..position + 2 + digits))
+                    .filter(|&value| value <= 0xff)
+                    .ok_or(LiteralError::InvalidHexEscape { position })?;
+                bytes.push(value as u8);
+                Ok(position + 2 + digits)
+            }
+            escape => Err(LiteralError::InvalidEscape { position, escape }),
+        }
+    }
+
+    fn encode(&self, bytes: &[u8]) -> Vec<u8> {
+        encode_with(self.opening(), bytes, |c| match c {
+            b'\\' | b'"' => Some(format!("\\{}", c as char)),
+            b'\n' => Some(String::from("\\n")),
+            b'\r' => Some(String::from("\\r")),
+            b'\t' => Some(String::from("\\t")),
+            b' '..=b'~' => None,
+            _ => Some(format!("\\{:03o}", c)),
+        })
+    }
+
+    fn skip_len(&self, cs: &[u8]) -> Option<usize> {
+        c_like_skip_len(cs, false)
+    }
+}
+
+impl LiteralDialect for JsonDialect {
+    fn decode_escape(
+        &self,
+        cs: &[u8],
+        position: usize,
+        bytes: &mut Vec<u8>,
+    ) -> Result<usize, LiteralError> {
+        let escape = escape_at(cs, position)?;
+        if let Some(c) = simple_escape(JSON_ESCAPES, escape) {
+            bytes.push(c);
+            return Ok(position + 2);
+        }
+        match escape {
+            b'u' => {
+                let error = LiteralError::InvalidUnicodeEscape { position };
+                let unit = |start: usize| {
+                    if cs.get(start..start + 2) == Some(b"\\u") {
+                        hex_number(cs.get(start + 2..start + 6))
+                    } else {
+                        None
+                    }
+                };
+                let high = unit(position).ok_or_else(|| error.clone())?;
+                let (code, end) = match high {
+                    0xd800..=0xdbff => {
+                        let low = unit(position + 6)
+                            .filter(|low| (0xdc00..=0xdfff).contains(low))
+                            .ok_or_else(|| error.clone())?;
+                        (
+                            0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00),
+                            position + 12,
+                        )
+                    }
+                    _ => (high, position + 6),
+                };
+                push_char(bytes, std::char::from_u32(code).ok_or(error)?);
+                Ok(end)
+            }
+            escape => Err(LiteralError::InvalidEscape { position, escape }),
+        }
+    }
+
+    fn encode(&self, bytes: &[u8]) -> Vec<u8> {
+        encode_with(self.opening(), bytes, |c| match c {
+            b'\\' | b'"' => Some(format!("\\{}", c as char)),
+            0x08 => Some(String::from("\\b")),
+            0x0c => Some(String::from("\\f")),
+            b'\n' => Some(String::from("\\n")),
+            b'\r' => Some(String::from("\\r")),
+            b'\t' => Some(String::from("\\t")),
+            0x00..=0x1f => Some(format!("\\u{:04x}", c)),
+            _ => None,
+        })
+    }
+}
+
+impl LiteralDialect for PythonRawDialect {
+    fn opening(&self) -> &'static [u8] {
+        b"r\""
+    }
+
+    fn decode_escape(
+        &self,
+        cs: &[u8],
+        position: usize,
+        bytes: &mut Vec<u8>,
+    ) -> Result<usize, LiteralError> {
+        let escape = escape_at(cs, position)?;
+        bytes.extend_from_slice(&[b'\\', escape]);
+        Ok(position + 2)
+    }
+
+    // Not every string fits in a raw literal, so encode as a regular one.
+    fn encode(&self, bytes: &[u8]) -> Vec<u8> {
+        encode_with(b"\"", bytes, |c| match c {
+            b'\\' | b'"' => Some(format!("\\{}", c as char)),
+            b'\n' => Some(String::from("\\n")),
+            b'\r' => Some(String::from("\\r")),
+            b'\t' => Some(String::from("\\t")),
+            0x00..=0x1f | 0x7f => Some(format!("\\x{:02x}", c)),
+            _ => None,
+        })
+    }
+
+    fn skip_len(&self, cs: &[u8]) -> Option<usize> {
+        match cs.first()? {
+            b'#' => Some(line_comment_len(cs)),
+            &quote @ (b'"' | b'\'') => {
+                let triple = [quote; 3];
+                let quote = if cs.starts_with(&triple) {
+                    &triple[..]
+                } else {
+                    &cs[..1]
+                };
+                Some(quoted_len(cs, quote))
+            }
+            _ => None,
+        }
+    }
+}
+
+fn decode_prefix(cs: &[u8], dialect: &dyn LiteralDialect) -> Result<DecodedLiteral, LiteralError> {
+    let opening = dialect.opening();
+    if !cs.starts_with(opening) {
+        return Err(LiteralError::MissingOpeningQuote);
+    }
+
+    let mut bytes = Vec::with_capacity(cs.len());
+    let mut i = opening.len();
+    while i < cs.len() {
+        match cs[i] {
+            b'"' => {
+                return Ok(DecodedLiteral {
+                    bytes,
+                    code_len: i + 1,
+                })
+            }
+            b'\\' => i = dialect.decode_escape(cs, i, &mut bytes)?,
+            c => {
+                bytes.push(c);
+                i += 1;
+            }
+        }
+    }
+
+    Err(LiteralError::Unterminated { position: cs.len() })
+}
+
+fn decode_any(
+    cs: &[u8],
+    dialect: &dyn LiteralDialect,
+) -> Option<Result<DecodedLiteral, LiteralError>> {
+    if cs.starts_with(dialect.opening()) {
+        Some(decode_prefix(cs, dialect))
+    } else {
+        dialect.decode_prefixed(cs)
+    }
+}
+
+pub fn decode_literal_with(
+    cs: &[u8],
+    dialect: &dyn LiteralDialect,
+) -> Result<DecodedLiteral, LiteralError> {
+    let literal = decode_any(cs, dialect).unwrap_or(Err(LiteralError::MissingOpeningQuote))?;
+    if literal.code_len < cs.len() {
+        return Err(LiteralError::TrailingCharacters {
+            position: literal.code_len,
+        });
+    }
+
+    Ok(literal)
+}
+
+pub fn decode_literal(cs: &[u8]) -> Result<DecodedLiteral, LiteralError> {
+    decode_literal_with(cs, &PuzzleDialect)
+}
+
+pub fn encode_literal_with(bytes: &[u8], dialect: &dyn LiteralDialect) -> Vec<u8> {
+    dialect.encode(bytes)
+}
+
+pub fn encode_literal(bytes: &[u8]) -> Vec<u8> {
+    encode_with(b"\"", bytes, |c| match c {
+        b'\\' | b'"' => Some(format!("\\{}", c as char)),
+        _ => None,
+    })
+}
+
+fn is_identifier_char(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || c == b'_'
+}
+
+pub fn scan_literals<'a>(
+    source: &'a [u8],
+    dialect: &dyn LiteralDialect,
+) -> Result<Vec<(usize, &'a [u8])>, LiteralError> {
+    let mut literals = Vec::new();
+    let mut i = 0;
+    while i < source.len() {
+        let cs = &source[i..];
+        if let Some(len) = dialect.skip_len(cs) {
+            i += len;
+            continue;
+        }
+
+        let starts_word = i == 0 || !is_identifier_char(source[i - 1]);
+        let literal = if starts_word || !is_identifier_char(cs[0]) {
+            decode_any(cs, dialect)
+        } else {
+            None
+        };
+        match literal {
+            Some(literal) => {
+                let code_len = literal.map_err(|e| e.shifted(i))?.code_len;
+                literals.push((i, &cs[..code_len]));
+                i += code_len;
+            }
+            None => i += 1,
+        }
+    }
+
+    Ok(literals)
+}
+
+fn count_str_memory_len(cs: &[u8], dialect: &dyn LiteralDialect) -> Result<usize, LiteralError> {
+    let literal = decode_literal_with(cs, dialect)?;
+    Ok(literal.code_len - literal.memory_len())
+}
+
+fn count_str_code_len(cs: &[u8], dialect: &dyn LiteralDialect) -> Result<usize, LiteralError> {
+    Ok(encode_literal_with(cs, dialect).len() - cs.len())
+}
+
+fn count_source_len(
+    source: &[u8],
+    dialect: &dyn LiteralDialect,
+    count_len: fn(&[u8], &dyn LiteralDialect) -> Result<usize, LiteralError>,
+) -> Result<usize, LiteralError> {
+    scan_literals(source, dialect)?
+        .into_iter()
+        .map(|(offset, cs)| count_len(cs, dialect).map_err(|e| e.shifted(offset)))
+        .sum()
+}
+
+fn locate_error(source: &[u8], e: LiteralError) -> (usize, LiteralError) {
+    let position = e.position().unwrap_or(0).min(source.len());
+    let line_start = source[..position]
+        .iter()
+        .rposition(|&c| c == b'\n')
+        .map_or(0, |i| i + 1);
+    let line = source[..line_start].iter().filter(|&&c| c == b'\n').count() + 1;
+
+    (line, e.map_position(|position| position - line_start))
+}
+
+fn solve(
+    mut input: impl std::io::BufRead,
+    dialect: &dyn LiteralDialect,
+    scan: bool,
+    count_len: fn(&[u8], &dyn LiteralDialect) -> Result<usize, LiteralError>,
+) -> usize {
+    if scan {
+        let mut source = Vec::new();
+        input.read_to_end(&mut source).unwrap();
+        return count_source_len(&source, dialect, count_len).unwrap_or_else(|e| {
+            let (line, e) = locate_error(&source, e);
+            panic!("Line {}: {}", line, e)
+        });
+    }
+
     input
         .lines()
-        .map(|line| count_len(line.unwrap().as_bytes()))
+        .enumerate()
+        .map(|(n, line)| {
+            count_len(line.unwrap().as_bytes(), dialect)
+                .unwrap_or_else(|e| panic!("Line {}: {}", n + 1, e))
+        })
         .sum()
 }
 
+pub fn solve_a_with_dialect(input: impl std::io::BufRead, dialect_name: &str, scan: bool) -> usize {
+    let dialect = make_dialect(dialect_name).expect("Unknown literal dialect");
+    solve(input, dialect.as_ref(), scan, count_str_memory_len)
+}
+
+pub fn solve_b_with_dialect(input: impl std::io::BufRead, dialect_name: &str, scan: bool) -> usize {
+    let dialect = make_dialect(dialect_name).expect("Unknown literal dialect");
+    solve(input, dialect.as_ref(), scan, count_str_code_len)
+}
+
 pub fn solve_a(input: impl std::io::BufRead) -> usize {
-    solve(input, count_str_memory_len)
+    solve(input, &PuzzleDialect, false, count_str_memory_len)
 }
 
 pub fn solve_b(input: impl std::io::BufRead) -> usize {
-    solve(input, count_str_code_len)
+    solve(input, &PuzzleDialect, false, count_str_code_len)
 }
 
 #[cfg(test)]
@@ -58,5 +803,278 @@ mod tests {
         assert_eq!(4, solve_b(&br#""abc""#[..]));
         assert_eq!(6, solve_b(&br#""aaa\"aaa""#[..]));
         assert_eq!(5, solve_b(&br#""\x27""#[..]));
+        assert_eq!(4, solve_b("\"a\tb\u{e9}\"".as_bytes()));
+    }
+
+    #[test]
+    fn check_decoding() {
+        let decode = |cs: &[u8]| decode_literal(cs).map(|literal| literal.bytes);
+
+        assert_eq!(Ok(b"".to_vec()), decode(br#""""#));
+        assert_eq!(Ok(b"a\"b\\c'".to_vec()), decode(br#""a\"b\\c\x27""#));
+        assert_eq!(Ok(vec![0xff, 0x0a]), decode(br#""\xFF\x0a""#));
+
+        assert_eq!(Err(LiteralError::MissingOpeningQuote), decode(b"abc\""));
+        assert_eq!(Err(LiteralError::MissingOpeningQuote), decode(b""));
+        assert_eq!(
+            Err(LiteralError::Unterminated { position: 4 }),
+            decode(br#""abc"#)
+        );
+        assert_eq!(
+            Err(LiteralError::Unterminated { position: 3 }),
+            decode(br#""a\"#)
+        );
+        assert_eq!(
+            Err(LiteralError::Unterminated { position: 5 }),
+            decode(br#""ab\""#)
+        );
+        assert_eq!(
+            Err(LiteralError::TrailingCharacters { position: 3 }),
+            decode(br#""a"b""#)
+        );
+        assert_eq!(
+            Err(LiteralError::InvalidEscape {
+                position: 2,
+                escape: b'n'
+            }),
+            decode(br#""a\n""#)
+        );
+        assert_eq!(
+            Err(LiteralError::InvalidHexEscape { position: 1 }),
+            decode(br#""\x4""#)
+        );
+        assert_eq!(
+            Err(LiteralError::InvalidHexEscape { position: 1 }),
+            decode(br#""\xg0""#)
+        );
+        assert_eq!(
+            "Invalid escape '\\n' at position 2",
+            decode(br#""a\n""#).unwrap_err().to_string()
+        );
+    }
+
+    #[test]
+    fn check_encoding() {
+        assert_eq!(br#""""#.to_vec(), encode_literal(b""));
+        assert_eq!(b"\"\\\"\\\\x\x00\"".to_vec(), encode_literal(b"\"\\x\x00"));
+
+        for bytes in &[&b"abc"[..], b"\"\\", &[0, 127, 255]] {
+            assert_eq!(
+                bytes.to_vec(),
+                decode_literal(&encode_literal(bytes)).unwrap().bytes
+            );
+        }
+    }
+
+    #[test]
+    fn check_long_line() {
+        let mut line = vec![b'"'];
+        line.extend(br#"\x41\\a"#.repeat(200_000));
+        line.push(b'"');
+
+        let literal = decode_literal(&line).unwrap();
+        assert_eq!(600_000, literal.memory_len());
+        assert_eq!(
+            800_002,
+            count_str_memory_len(&line, &PuzzleDialect).unwrap()
+        );
+        assert_eq!(600_004, count_str_code_len(&line, &PuzzleDialect).unwrap());
+    }
+
+    #[test]
+    fn check_dialects() {
+        let decode = |cs: &[u8], name: &str| {
+            decode_literal_with(cs, make_dialect(name).unwrap().as_ref())
+                .map(|literal| literal.bytes)
+        };
+
+        assert_eq!(
+            Ok("a\n\u{1F600}A'".as_bytes().to_vec()),
+            decode(br#""a\n\u{1F600}\x41\'""#, "rust")
+        );
+        assert_eq!(
+            Err(LiteralError::InvalidHexEscape { position: 1 }),
+            decode(br#""\x80""#, "rust")
+        );
+        for cs in &[&br#""\u{110000}""#[..], br#""\u{1234567}""#, br#""\u1234""#] {
+            assert_eq!(
+                Err(LiteralError::InvalidUnicodeEscape { position: 1 }),
+                decode(cs, "rust")
+            );
+        }
+
+        assert_eq!(
+            Ok(vec![b'A', b'A', 0, b'?', 0x07, 1, b'8']),
+            decode(br#""\101\x41\0\?\a\18""#, "c")
+        );
+        assert_eq!(
+            Err(LiteralError::InvalidHexEscape { position: 1 }),
+            decode(br#""\x4142""#, "c")
+        );
+        assert_eq!(
+            Err(LiteralError::InvalidOctalEscape { position: 1 }),
+            decode(br#""\777""#, "c")
+        );
+
+        assert_eq!(
+            Ok("\u{e9}\u{1F600}/".as_bytes().to_vec()),
+            decode(br#""\u00e9\ud83d\ude00\/""#, "json")
+        );
+        for cs in &[&br#""\ud83d""#[..], br#""\ude00""#, br#""\u12""#] {
+            assert_eq!(
+                Err(LiteralError::InvalidUnicodeEscape { position: 1 }),
+                decode(cs, "json")
+            );
+        }
+        assert_eq!(
+            Err(LiteralError::InvalidEscape {
+                position: 1,
+                escape: b'x'
+            }),
+            decode(br#""\x41""#, "json")
+        );
+
+        assert_eq!(
+            Ok(br#"a\"b\n"#.to_vec()),
+            decode(br#"r"a\"b\n""#, "python-raw")
+        );
+        assert_eq!(
+            Err(LiteralError::Unterminated { position: 4 }),
+            decode(br#"r"\""#, "python-raw")
+        );
+        assert_eq!(
+            Err(LiteralError::MissingOpeningQuote),
+            decode(br#""abc""#, "python-raw")
+        );
+
+        assert!(make_dialect("cobol").is_none());
+    }
+
+    #[test]
+    fn check_dialect_encoding() {
+        let samples: Vec<&[u8]> = vec![
+            b"abc",
+            b"\"\\",
+            &[0, 7, 8, 9, 10, 11, 12, 13, 27, 127, 128, 255],
+            "\u{e9}\u{1F600}".as_bytes(),
+        ];
+        for name in &["puzzle", "rust", "c", "json"] {
+            let dialect = make_dialect(name).unwrap();
+            for bytes in &samples {
+                let encoded = encode_literal_with(bytes, dialect.as_ref());
+                let decoded = decode_literal_with(&encoded, dialect.as_ref()).unwrap();
+                assert_eq!(bytes.to_vec(), decoded.bytes, "{}", name);
+            }
+        }
+
+        assert_eq!(
+            br#""a\"\n\x01""#.to_vec(),
+            encode_literal_with(b"a\"\n\x01", &RustDialect)
+        );
+        assert_eq!(
+            br#"b"\x7f\xff""#.to_vec(),
+            encode_literal_with(b"\x7f\xff", &RustByteDialect)
+        );
+        assert_eq!(
+            br#""\t\001\377""#.to_vec(),
+            encode_literal_with(b"\x09\x01\xff", &CDialect)
+        );
+        assert_eq!(
+            br#""\b\u001b""#.to_vec(),
+            encode_literal_with(b"\x08\x1b", &JsonDialect)
+        );
+        assert_eq!(
+            br#""a\\\"b\\\n\x01""#.to_vec(),
+            encode_literal_with(b"a\\\"b\\\n\x01", &PythonRawDialect)
+        );
+    }
+
+    #[test]
+    fn check_scanning() {
+        let scan = |source: &[u8], dialect: &dyn LiteralDialect| {
+            scan_literals(source, dialect)
+                .unwrap()
+                .into_iter()
+                .map(|(_, cs)| String::from_utf8(cs.to_vec()).unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        let line = br#"let s = format!("{}\n", b"x", '"', "\x41");"#;
+        assert_eq!(
+            vec![r#""{}\n""#, r#"b"x""#, r#""\x41""#],
+            scan(line, &RustDialect)
+        );
+        assert_eq!(
+            Ok(11),
+            count_source_len(line, &RustDialect, count_str_memory_len)
+        );
+
+        let source = br##"fn f<'a>(x: &'a str) -> char { // "not a literal
+    /* "nor /* this */ one" */ let c = '\''; let d = b'\\';
+    let r = r#"raw "quoted" \q"#; let b = br"\x"; let n = b"\xff";
+    let s = "multi
+        line \
+        continued";
+    'outer: loop { break 'outer; } r#type
+}"##;
+        assert_eq!(
+            vec![
+                r###"r#"raw "quoted" \q"#"###,
+                r#"br"\x""#,
+                r#"b"\xff""#,
+                "\"multi\n        line \\\n        continued\"",
+            ],
+            scan(source, &RustDialect)
+        );
+        let continued = decode_literal_with(b"\"a\\\n   b\"", &RustDialect).unwrap();
+        assert_eq!(b"ab".to_vec(), continued.bytes);
+        let bytes = decode_literal_with(br#"b"\xff""#, &RustDialect).unwrap();
+        assert_eq!(vec![0xff], bytes.bytes);
+
+        assert_eq!(
+            Err(LiteralError::InvalidEscape {
+                position: 15,
+                escape: b'q'
+            }),
+            count_source_len(
+                br#"x = "ok"; y = "\q";"#,
+                &RustDialect,
+                count_str_memory_len
+            )
+        );
+        let source = b"let x = 1;\nlet y = \"a\\qb\";\n";
+        assert_eq!(
+            (
+                2,
+                LiteralError::InvalidEscape {
+                    position: 10,
+                    escape: b'q'
+                }
+            ),
+            locate_error(source, scan_literals(source, &RustDialect).unwrap_err())
+        );
+        assert_eq!(
+            Err(LiteralError::Unterminated { position: 15 }),
+            scan_literals(br##"let r = r#"abc""##, &RustDialect)
+        );
+
+        let c = b"/* \"skip\" */ puts(\"a\\\nb\"); // \"no\"\nchar q = '\"';";
+        assert_eq!(vec!["\"a\\\nb\""], scan(c, &CDialect));
+        assert_eq!(Ok(4), count_source_len(c, &CDialect, count_str_memory_len));
+
+        let python = br#"re.compile(r"\d+\"", "bar", r"\w")  # r"comment"
+b = br"\s" + '''r"x''' + rb"y""#;
+        assert_eq!(
+            vec![r#"r"\d+\"""#, r#"r"\w""#],
+            scan(python, &PythonRawDialect)
+        );
+
+        let input = &b"\"abc\"\n\"\\x27\""[..];
+        assert_eq!(solve_a(input), solve_a_with_dialect(input, "puzzle", false));
+        assert_eq!(solve_b(input), solve_b_with_dialect(input, "puzzle", false));
+        assert_eq!(0, solve_a_with_dialect(&b"no literals here"[..], "c", true));
+        let python = &b"x = r\"abc\""[..];
+        assert_eq!(3, solve_a_with_dialect(python, "python-raw", true));
+        assert_eq!(4, solve_b_with_dialect(python, "python-raw", true));
     }
 }
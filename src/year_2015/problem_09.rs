@@ -1,3 +1,4 @@
+use crate::utils::graph::{best_hamiltonian_path, DenseGraph, NodeInterner, WeightedGraph};
 use crate::utils::parsing::{parse_decimal, parse_str_alpha, parse_ws};
 use itertools::Itertools;
 use nom::bytes::complete::tag;
@@ -6,8 +7,7 @@ type NomResult<'a, T> = nom::IResult<&'a str, T>;
 type City = String;
 type Distance = usize;
 
-type CitySet = std::collections::BTreeSet<City>;
-type RoutesMap<'a> = std::collections::HashMap<(&'a City, &'a City), Distance>;
+type IsBetter = crate::utils::graph::IsBetter<Distance>;
 
 #[derive(Debug, Eq, PartialEq)]
 struct Route {
@@ -16,6 +16,32 @@ struct Route {
     distance: Distance,
 }
 
+#[derive(Debug, Eq, PartialEq)]
+struct Trip {
+    cities: Vec<City>,
+    legs: Vec<Route>,
+    distance: Distance,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum RouteError {
+    NoCities,
+    NoRoute { missing_pairs: Vec<(City, City)> },
+}
+
+impl std::fmt::Display for RouteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            RouteError::NoCities => write!(f, "No cities to visit"),
+            RouteError::NoRoute { missing_pairs } => write!(
+                f,
+                "Route not found, missing distances: {}",
+                format_city_pairs(missing_pairs)
+            ),
+        }
+    }
+}
+
 fn parse_route(input: &str) -> Route {
     fn parse_city(input: &str) -> NomResult<City> {
         parse_ws(parse_str_alpha)(input)
@@ -41,60 +67,110 @@ fn parse_route(input: &str) -> Route {
     }
 }
 
-fn make_cities_set(routes: &[Route]) -> CitySet {
-    routes
+fn make_distance_graph(routes: &[Route]) -> (NodeInterner, DenseGraph<Distance>) {
+    let mut cities = NodeInterner::new();
+    let edges: Vec<_> = routes
         .iter()
-        .flat_map(|r| std::iter::once(r.to.clone()).chain(std::iter::once(r.from.clone())))
-        .collect()
+        .map(|route| {
+            let from = cities.intern(&route.from);
+            let to = cities.intern(&route.to);
+            (from, to, route.distance)
+        })
+        .collect();
+
+    let mut graph = DenseGraph::new(cities.len());
+    for (from, to, distance) in edges {
+        graph.add_undirected_edge(from, to, distance);
+    }
+
+    (cities, graph)
 }
 
-fn make_routes_map(routes: &[Route]) -> RoutesMap {
-    routes
+fn format_city_pairs(pairs: &[(City, City)]) -> String {
+    pairs
         .iter()
-        .map(|route| ((&route.from, &route.to), route.distance))
-        .collect()
+        .map(|(from, to)| format!("{} - {}", from, to))
+        .join(", ")
 }
 
-fn calc_total_distance(cities: &[&City], routes_map: &RoutesMap) -> usize {
-    cities
-        .iter()
-        .tuple_windows::<(_, _)>()
-        .map(|(&c1, &c2)| routes_map.get(&(c1, c2)).unwrap())
-        .sum()
+fn find_missing_pairs(cities: &NodeInterner, graph: &DenseGraph<Distance>) -> Vec<(City, City)> {
+    (0..cities.len())
+        .tuple_combinations()
+        .filter(|&(from, to)| graph.weight(from, to).is_none())
+        .map(|(from, to)| (cities.name(from).to_string(), cities.name(to).to_string()))
+        .collect()
 }
 
-fn solve(input: impl std::io::BufRead) -> Vec<usize> {
+fn find_trip(input: impl std::io::BufRead, is_better: IsBetter) -> Result<Trip, RouteError> {
     let routes = input
         .lines()
-        .flat_map(|line| {
-            let r1 = parse_route(line.unwrap().as_str());
-            let r2 = Route {
-                from: r1.to.clone(),
-                to: r1.from.clone(),
-                distance: r1.distance,
-            };
-            std::iter::once(r1).chain(std::iter::once(r2))
-        })
+        .map(|line| parse_route(line.unwrap().as_str()))
         .collect::<Vec<Route>>();
 
-    let routes_map = make_routes_map(&routes);
-    let cities = make_cities_set(&routes);
+    let (cities, graph) = make_distance_graph(&routes);
+    if cities.is_empty() {
+        return Err(RouteError::NoCities);
+    }
+
+    let (distance, path) =
+        best_hamiltonian_path(&graph, is_better).ok_or_else(|| RouteError::NoRoute {
+            missing_pairs: find_missing_pairs(&cities, &graph),
+        })?;
 
-    let all_distances = cities
+    let legs = path
         .iter()
-        .permutations(cities.len())
-        .map(|cities_permutation| calc_total_distance(&cities_permutation, &routes_map))
+        .tuple_windows()
+        .map(|(&from, &to)| Route {
+            from: cities.name(from).to_string(),
+            to: cities.name(to).to_string(),
+            distance: graph.weight(from, to).unwrap(),
+        })
         .collect();
 
-    all_distances
+    Ok(Trip {
+        cities: path
+            .into_iter()
+            .map(|city| cities.name(city).to_string())
+            .collect(),
+        legs,
+        distance,
+    })
+}
+
+fn solve(input: impl std::io::BufRead, is_better: IsBetter) -> Option<Trip> {
+    find_trip(input, is_better).ok()
+}
+
+fn explain(input: impl std::io::BufRead, is_better: IsBetter) -> Result<String, RouteError> {
+    let trip = find_trip(input, is_better)?;
+
+    let route = trip.cities.join(" -> ");
+    let legs = trip
+        .legs
+        .iter()
+        .map(|leg| format!("{} -> {} = {}", leg.from, leg.to, leg.distance));
+    let total = format!("Total = {}", trip.distance);
+
+    Ok(std::iter::once(route)
+        .chain(legs)
+        .chain(std::iter::once(total))
+        .join("\n"))
 }
 
-pub fn solve_a(input: impl std::io::BufRead) -> usize {
-    solve(input).into_iter().min().unwrap()
+pub fn solve_a(input: impl std::io::BufRead) -> Option<usize> {
+    solve(input, |a, b| a < b).map(|trip| trip.distance)
 }
 
-pub fn solve_b(input: impl std::io::BufRead) -> usize {
-    solve(input).into_iter().max().unwrap()
+pub fn solve_b(input: impl std::io::BufRead) -> Option<usize> {
+    solve(input, |a, b| a > b).map(|trip| trip.distance)
+}
+
+pub fn explain_a(input: impl std::io::BufRead) -> Result<String, RouteError> {
+    explain(input, |a, b| a < b)
+}
+
+pub fn explain_b(input: impl std::io::BufRead) -> Result<String, RouteError> {
+    explain(input, |a, b| a > b)
 }
 
 #[cfg(test)]
@@ -116,7 +192,7 @@ mod tests {
     #[test]
     fn check_a() {
         assert_eq!(
-            605,
+            Some(605),
             solve_a(
                 &b"London to Dublin = 464\nLondon to Belfast = 518\nDublin to Belfast = 141"[..]
             )
@@ -124,5 +200,150 @@ mod tests {
     }
 
     #[test]
-    fn check_b() {}
+    fn check_b() {
+        assert_eq!(
+            Some(982),
+            solve_b(
+                &b"London to Dublin = 464\nLondon to Belfast = 518\nDublin to Belfast = 141"[..]
+            )
+        );
+    }
+
+    #[test]
+    fn check_route() {
+        let input = b"London to Dublin = 464\nLondon to Belfast = 518\nDublin to Belfast = 141";
+
+        let shortest = solve(&input[..], |a, b| a < b).unwrap();
+        assert_eq!(605, shortest.distance);
+        assert!(
+            shortest.cities == ["London", "Dublin", "Belfast"]
+                || shortest.cities == ["Belfast", "Dublin", "London"]
+        );
+
+        let longest = solve(&input[..], |a, b| a > b).unwrap();
+        assert_eq!(982, longest.distance);
+        assert!(
+            longest.cities == ["Dublin", "London", "Belfast"]
+                || longest.cities == ["Belfast", "London", "Dublin"]
+        );
+    }
+
+    #[test]
+    fn check_explain() {
+        let input = b"London to Dublin = 464\nLondon to Belfast = 518\nDublin to Belfast = 141";
+        let trip = solve(&input[..], |a, b| a < b).unwrap();
+        assert_eq!(
+            vec![141, 464],
+            trip.legs
+                .iter()
+                .map(|leg| leg.distance)
+                .sorted()
+                .collect::<Vec<_>>()
+        );
+
+        assert_eq!(
+            Ok(String::from(
+                "Belfast -> London -> Dublin\nBelfast -> London = 518\nLondon -> Dublin = 464\nTotal = 982"
+            )),
+            explain_b(&input[..])
+        );
+
+        let input = b"London to Dublin = 464\nParis to Berlin = 878";
+        assert_eq!(
+            Err(RouteError::NoRoute {
+                missing_pairs: vec![
+                    (String::from("London"), String::from("Paris")),
+                    (String::from("London"), String::from("Berlin")),
+                    (String::from("Dublin"), String::from("Paris")),
+                    (String::from("Dublin"), String::from("Berlin")),
+                ]
+            }),
+            find_trip(&input[..], |a, b| a < b)
+        );
+        assert_eq!(None, solve_a(&input[..]));
+        assert_eq!(
+            "Route not found, missing distances: London - Paris, London - Berlin, Dublin - Paris, Dublin - Berlin",
+            explain_a(&input[..]).unwrap_err().to_string()
+        );
+
+        assert_eq!(Err(RouteError::NoCities), find_trip(&b""[..], |a, b| a < b));
+        assert_eq!(None, solve_a(&b""[..]));
+        assert_eq!(None, solve_b(&b""[..]));
+        assert_eq!(
+            "No cities to visit",
+            explain_a(&b""[..]).unwrap_err().to_string()
+        );
+    }
+
+    fn calc_brute_force_distances(graph: &DenseGraph<Distance>) -> Vec<Distance> {
+        (0..graph.node_count())
+            .permutations(graph.node_count())
+            .filter_map(|route| {
+                route
+                    .iter()
+                    .tuple_windows()
+                    .map(|(&from, &to)| graph.weight(from, to))
+                    .sum::<Option<Distance>>()
+            })
+            .collect()
+    }
+
+    fn calc_route_distance(graph: &DenseGraph<Distance>, route: &[usize]) -> Distance {
+        route
+            .iter()
+            .tuple_windows()
+            .map(|(&from, &to)| graph.weight(from, to).unwrap())
+            .sum()
+    }
+
+    #[test]
+    fn check_against_brute_force() {
+        let mut seed: usize = 42;
+        let mut next_random = move || {
+            seed = (seed * 1103515245 + 12345) % (1 << 31);
+            seed % 100
+        };
+
+        for n in 1..=7 {
+            let mut graph = DenseGraph::new(n);
+            for (from, to) in (0..n).tuple_combinations() {
+                let distance = next_random();
+                // leave some pairs unconnected to exercise partial graphs
+                if distance >= 10 {
+                    graph.add_undirected_edge(from, to, distance);
+                }
+            }
+
+            let brute_force = calc_brute_force_distances(&graph);
+            let shortest = best_hamiltonian_path(&graph, |a, b| a < b);
+            let longest = best_hamiltonian_path(&graph, |a, b| a > b);
+
+            assert_eq!(
+                brute_force.iter().min().copied(),
+                shortest.as_ref().map(|(distance, _)| *distance)
+            );
+            assert_eq!(
+                brute_force.iter().max().copied(),
+                longest.as_ref().map(|(distance, _)| *distance)
+            );
+
+            for (distance, route) in shortest.iter().chain(longest.iter()) {
+                assert_eq!(n, route.iter().unique().count());
+                assert_eq!(*distance, calc_route_distance(&graph, route));
+            }
+        }
+    }
+
+    #[test]
+    fn check_many_cities() {
+        let n = 20;
+        let mut graph = DenseGraph::new(n);
+        for (from, to) in (0..n).tuple_combinations() {
+            graph.add_undirected_edge(from, to, to - from);
+        }
+
+        let (distance, route) = best_hamiltonian_path(&graph, |a, b| a < b).unwrap();
+        assert_eq!(n - 1, distance);
+        assert!(route == (0..n).collect::<Vec<_>>() || route == (0..n).rev().collect::<Vec<_>>());
+    }
 }
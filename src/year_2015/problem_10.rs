@@ -1,40 +1,254 @@
+use crate::utils::decimal::Decimal;
 use itertools::Itertools;
+use std::collections::{HashMap, HashSet};
+
 type Digit = u8;
+type Element = Vec<Digit>;
+type ElementId = usize;
+
+const SPLIT_PREFIX_LEN: usize = 16;
 
-fn usize_to_digit(n: usize) -> u8 {
-    assert!(n <= 9);
-    b'0' + (n as u8)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RunLengthTransform {
+    base: u32,
 }
 
-fn look_and_say(digits: &Vec<Digit>) -> Vec<Digit> {
-    digits
-        .iter()
-        .map(|digit| (1, digit))
-        .coalesce(|a, b| match (a, b) {
-            ((occurrences, digit1), (_, digit2)) if digit1 == digit2 => {
-                Ok((occurrences + 1, digit1))
+impl RunLengthTransform {
+    pub const DECIMAL: RunLengthTransform = RunLengthTransform { base: 10 };
+
+    pub fn new(base: u32) -> Self {
+        assert!((2..=36).contains(&base), "Unsupported base: {}", base);
+        RunLengthTransform { base }
+    }
+
+    fn push_count(&self, output: &mut Vec<u8>, count: usize) {
+        let start = output.len();
+        let mut count = count;
+        loop {
+            let digit = std::char::from_digit((count % self.base as usize) as u32, self.base);
+            output.push(digit.unwrap() as u8);
+            count /= self.base as usize;
+            if count == 0 {
+                break;
             }
-            pair @ _ => Err(pair),
-        })
-        .flat_map(|(n, &digit)| std::iter::once(usize_to_digit(n)).chain(std::iter::once(digit)))
-        .collect()
+        }
+        output[start..].reverse();
+    }
+
+    pub fn apply(&self, symbols: &[u8]) -> Vec<u8> {
+        let mut output = Vec::with_capacity(symbols.len() * 2);
+        for (count, &symbol) in symbols.iter().dedup_with_count() {
+            self.push_count(&mut output, count);
+            output.push(symbol);
+        }
+
+        output
+    }
+
+    pub fn generation_lengths(&self, seed: &[u8]) -> GenerationLengths {
+        GenerationLengths {
+            transform: *self,
+            current: seed.to_vec(),
+            started: false,
+        }
+    }
 }
 
-fn solve(input: impl std::io::BufRead, iterations: usize) -> usize {
-    let input_digits: Vec<Digit> = input.bytes().try_collect().unwrap();
+pub struct GenerationLengths {
+    transform: RunLengthTransform,
+    current: Vec<u8>,
+    started: bool,
+}
+
+impl Iterator for GenerationLengths {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.started {
+            self.current = self.transform.apply(&self.current);
+        }
+        self.started = true;
+
+        Some(self.current.len())
+    }
+}
+
+fn look_and_say(digits: &Vec<Digit>) -> Vec<Digit> {
+    RunLengthTransform::DECIMAL.apply(digits)
+}
+
+pub fn solve_direct(input: impl std::io::BufRead, iterations: usize) -> usize {
+    solve_with_transform(input, RunLengthTransform::DECIMAL, iterations)
+}
+
+pub fn solve_with_transform(
+    input: impl std::io::BufRead,
+    transform: RunLengthTransform,
+    iterations: usize,
+) -> usize {
+    let input_symbols: Vec<u8> = input.bytes().try_collect().unwrap();
 
-    itertools::iterate(input_digits, look_and_say)
+    transform
+        .generation_lengths(&input_symbols)
         .nth(iterations)
         .unwrap()
-        .len()
 }
 
-pub fn solve_a(input: impl std::io::BufRead) -> usize {
-    solve(input, 40)
+fn without_last_run(digits: &[Digit]) -> &[Digit] {
+    match digits.last() {
+        Some(last) => {
+            let run_start = digits.iter().rposition(|d| d != last).map_or(0, |i| i + 1);
+            &digits[..run_start]
+        }
+        None => digits,
+    }
+}
+
+fn can_split(left_last: Digit, right: &[Digit]) -> bool {
+    let mut complete = right.len() <= SPLIT_PREFIX_LEN;
+    let mut prefix = right[..right.len().min(SPLIT_PREFIX_LEN)].to_vec();
+    let mut seen = HashSet::new();
+
+    loop {
+        match prefix.first() {
+            None => return false,
+            Some(&first) if first == left_last => return false,
+            _ => {}
+        }
+
+        if !seen.insert((prefix.clone(), complete)) {
+            return true;
+        }
+
+        let reliable = if complete {
+            &prefix[..]
+        } else {
+            without_last_run(&prefix)
+        };
+        let mut next = look_and_say(&reliable.to_vec());
+        complete = complete && next.len() <= SPLIT_PREFIX_LEN;
+        next.truncate(SPLIT_PREFIX_LEN);
+        prefix = next;
+    }
 }
 
-pub fn solve_b(input: impl std::io::BufRead) -> usize {
-    solve(input, 50)
+fn split_elements(digits: &[Digit]) -> Vec<Element> {
+    let mut elements = Vec::new();
+    let mut start = 0;
+    for i in 1..digits.len() {
+        if digits[i - 1] != digits[i] && can_split(digits[i - 1], &digits[i..]) {
+            elements.push(digits[start..i].to_vec());
+            start = i;
+        }
+    }
+    if start < digits.len() {
+        elements.push(digits[start..].to_vec());
+    }
+
+    elements
+}
+
+#[derive(Default)]
+struct ConwayEngine {
+    elements: Vec<Element>,
+    ids: HashMap<Element, ElementId>,
+    decays: Vec<Option<Vec<ElementId>>>,
+}
+
+impl ConwayEngine {
+    fn intern(&mut self, element: Element) -> ElementId {
+        if let Some(&id) = self.ids.get(&element) {
+            return id;
+        }
+
+        let id = self.elements.len();
+        self.ids.insert(element.clone(), id);
+        self.elements.push(element);
+        self.decays.push(None);
+        id
+    }
+
+    fn decompose(&mut self, digits: &[Digit]) -> Vec<ElementId> {
+        split_elements(digits)
+            .into_iter()
+            .map(|element| self.intern(element))
+            .collect()
+    }
+
+    fn decay(&mut self, id: ElementId) -> Vec<ElementId> {
+        if let Some(decay) = &self.decays[id] {
+            return decay.clone();
+        }
+
+        let next = look_and_say(&self.elements[id]);
+        let decay = self.decompose(&next);
+        self.decays[id] = Some(decay.clone());
+        decay
+    }
+
+    fn step(&mut self, counts: &HashMap<ElementId, Decimal>) -> HashMap<ElementId, Decimal> {
+        let mut next = HashMap::new();
+        for (&id, count) in counts {
+            for child in self.decay(id) {
+                *next.entry(child).or_insert_with(Decimal::zero) += count.clone();
+            }
+        }
+
+        next
+    }
+
+    fn length(&self, counts: &HashMap<ElementId, Decimal>) -> Decimal {
+        counts
+            .iter()
+            .map(|(&id, count)| count.clone() * self.elements[id].len() as u32)
+            .sum()
+    }
+}
+
+pub struct ConwayLengths {
+    engine: ConwayEngine,
+    counts: HashMap<ElementId, Decimal>,
+    started: bool,
+}
+
+impl Iterator for ConwayLengths {
+    type Item = Decimal;
+
+    fn next(&mut self) -> Option<Decimal> {
+        if self.started {
+            self.counts = self.engine.step(&self.counts);
+        }
+        self.started = true;
+
+        Some(self.engine.length(&self.counts))
+    }
+}
+
+pub fn conway_lengths(input: impl std::io::BufRead) -> ConwayLengths {
+    let input_digits: Vec<Digit> = input.bytes().try_collect().unwrap();
+    let mut engine = ConwayEngine::default();
+    let mut counts = HashMap::new();
+    for id in engine.decompose(&input_digits) {
+        *counts.entry(id).or_insert_with(Decimal::zero) += Decimal::from(1);
+    }
+
+    ConwayLengths {
+        engine,
+        counts,
+        started: false,
+    }
+}
+
+pub fn solve_conway(input: impl std::io::BufRead, iterations: usize) -> Decimal {
+    conway_lengths(input).nth(iterations).unwrap()
+}
+
+pub fn solve_a(input: impl std::io::BufRead) -> Decimal {
+    solve_conway(input, 40)
+}
+
+pub fn solve_b(input: impl std::io::BufRead) -> Decimal {
+    solve_conway(input, 50)
 }
 
 #[cfg(test)]
@@ -51,6 +265,95 @@ mod tests {
 
     #[test]
     fn check_a() {
-        assert_eq!(6, super::solve(&b"1"[..], 4));
+        assert_eq!(6, super::solve_direct(&b"1"[..], 4));
+    }
+
+    #[test]
+    fn check_transform() {
+        use super::RunLengthTransform;
+
+        let apply = |transform: RunLengthTransform, symbols: &str| {
+            String::from_utf8(transform.apply(symbols.as_bytes())).unwrap()
+        };
+
+        assert_eq!("101", apply(RunLengthTransform::DECIMAL, "1111111111"));
+        assert_eq!(
+            "3a1b12c",
+            apply(RunLengthTransform::DECIMAL, "aaabcccccccccccc")
+        );
+        assert_eq!(
+            "3a1bcc",
+            apply(RunLengthTransform::new(16), "aaabcccccccccccc")
+        );
+        assert_eq!("", apply(RunLengthTransform::new(2), ""));
+
+        let binary = itertools::iterate(b"1".to_vec(), |symbols| {
+            RunLengthTransform::new(2).apply(symbols)
+        });
+        let binary: Vec<String> = binary
+            .take(5)
+            .map(|symbols| String::from_utf8(symbols).unwrap())
+            .collect();
+        assert_eq!(vec!["1", "11", "101", "111011", "11110101"], binary);
+
+        let lengths: Vec<usize> = RunLengthTransform::new(2)
+            .generation_lengths(b"1")
+            .take(5)
+            .collect();
+        assert_eq!(vec![1, 2, 3, 6, 8], lengths);
+
+        let mut lengths = RunLengthTransform::DECIMAL.generation_lengths(b"1");
+        assert_eq!(Some(2), lengths.nth(2));
+        assert_eq!(b"21".to_vec(), lengths.current);
+        assert_eq!(
+            8,
+            super::solve_with_transform(&b"1"[..], RunLengthTransform::new(2), 4)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Unsupported base: 37")]
+    fn check_unsupported_base() {
+        super::RunLengthTransform::new(37);
+    }
+
+    #[test]
+    fn check_splitting() {
+        let split = |digits: &[u8]| -> Vec<String> {
+            super::split_elements(digits)
+                .into_iter()
+                .map(|element| String::from_utf8(element).unwrap())
+                .collect()
+        };
+
+        assert_eq!(vec!["22"], split(b"22"));
+        assert_eq!(vec!["1"], split(b"1"));
+        assert_eq!(vec!["3113112221131112"], split(b"3113112221131112"));
+        assert_eq!(
+            vec!["11132", "13", "22", "12", "312211322212221121123222112"],
+            split(&super::look_and_say(
+                &b"13112221133211322112211213322112".to_vec()
+            ))
+        );
+    }
+
+    #[test]
+    fn check_conway() {
+        for seed in &["1", "3", "22", "1113222113", "3113322113", "9999"] {
+            let expected = itertools::iterate(seed.as_bytes().to_vec(), super::look_and_say)
+                .take(36)
+                .map(|digits| digits.len().to_string());
+            let actual = super::conway_lengths(seed.as_bytes()).map(|len| len.to_string());
+            assert!(expected.eq(actual.take(36)), "{}", seed);
+        }
+
+        let mut lengths = super::conway_lengths(&b"1"[..]);
+        assert_eq!("1", lengths.next().unwrap().to_string());
+        let initial = super::conway_lengths(&b"1"[..]).counts;
+        assert_eq!(initial, lengths.counts);
+        let length = lengths.nth(999).unwrap().to_string();
+        assert_eq!(116, length.len());
+
+        assert_eq!(92, lengths.counts.len());
     }
 }
@@ -1,104 +1,428 @@
-static PROHIBITED_LETTERS: &[u8; 3] = b"iol";
+use std::collections::HashMap;
 
-fn has_no_prohibited_letters(password: &[u8]) -> bool {
-    password.iter().all(|c| !PROHIBITED_LETTERS.contains(c))
+static PUZZLE_ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
+static PUZZLE_FORBIDDEN_LETTERS: &[u8] = b"iol";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PasswordRule {
+    ForbiddenLetters(Vec<u8>),
+    Straight(usize),
+    DistinctPairs(usize),
+    MinLength(usize),
+    MaxLength(usize),
 }
 
-fn has_three_increasing_letters(password: &[u8]) -> bool {
-    password.windows(3).any(|w| match w {
-        &[a, b, c, ..] if a + 1 == b && a + 2 == c => true,
-        _ => false,
-    })
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PasswordPolicy {
+    alphabet: Vec<u8>,
+    rules: Vec<PasswordRule>,
 }
 
-fn has_two_pairs_of_letters(password: &[u8]) -> bool {
-    password.windows(2).any(|w1| match w1 {
-        [a, b, ..] if a == b => password.windows(2).any(|w2| match w2 {
-            [x, y, ..] if x == y && x != a => true,
-            _ => false,
-        }),
-        _ => false,
-    })
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        PasswordPolicy::new(PUZZLE_ALPHABET)
+            .with_rule(PasswordRule::ForbiddenLetters(
+                PUZZLE_FORBIDDEN_LETTERS.to_vec(),
+            ))
+            .with_rule(PasswordRule::Straight(3))
+            .with_rule(PasswordRule::DistinctPairs(2))
+    }
 }
 
-fn is_secure_password(password: &Vec<u8>) -> bool {
-    has_no_prohibited_letters(password)
-        && has_three_increasing_letters(password)
-        && has_two_pairs_of_letters(password)
+fn count_distinct_pairs(password: &[u8]) -> usize {
+    let mut letters: Vec<u8> = password
+        .windows(2)
+        .filter(|w| w[0] == w[1])
+        .map(|w| w[0])
+        .collect();
+    letters.sort_unstable();
+    letters.dedup();
+    letters.len()
 }
 
-fn next_char(c: u8) -> u8 {
-    if !PROHIBITED_LETTERS.contains(&(c + 1)) {
-        c + 1
-    } else {
-        c + 2
+impl PasswordPolicy {
+    pub fn new(alphabet: &[u8]) -> Self {
+        let mut symbols = alphabet.to_vec();
+        symbols.sort_unstable();
+        symbols.dedup();
+        assert!(
+            !alphabet.is_empty() && symbols.len() == alphabet.len(),
+            "Alphabet must be non-empty and have no repeated symbols"
+        );
+
+        PasswordPolicy {
+            alphabet: alphabet.to_vec(),
+            rules: Vec::new(),
+        }
+    }
+
+    pub fn with_rule(mut self, rule: PasswordRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    fn rank(&self, c: u8) -> Option<usize> {
+        self.alphabet.iter().position(|&a| a == c)
+    }
+
+    fn is_allowed(&self, c: u8) -> bool {
+        self.rank(c).is_some()
+            && !self.rules.iter().any(|rule| match rule {
+                PasswordRule::ForbiddenLetters(letters) => letters.contains(&c),
+                _ => false,
+            })
+    }
+
+    fn first_symbol(&self) -> Option<u8> {
+        self.alphabet.iter().copied().find(|&c| self.is_allowed(c))
+    }
+
+    fn next_symbol(&self, c: u8) -> Option<u8> {
+        let rank = self.rank(c).expect("Invalid password character");
+        self.alphabet[rank + 1..]
+            .iter()
+            .copied()
+            .find(|&c| self.is_allowed(c))
+    }
+
+    fn min_length(&self) -> usize {
+        self.rules
+            .iter()
+            .filter_map(|rule| match rule {
+                PasswordRule::MinLength(len) => Some(*len),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn max_length(&self) -> Option<usize> {
+        self.rules
+            .iter()
+            .filter_map(|rule| match rule {
+                PasswordRule::MaxLength(len) => Some(*len),
+                _ => None,
+            })
+            .min()
+    }
+
+    fn has_straight(&self, password: &[u8], len: usize) -> bool {
+        len <= 1
+            || password.windows(len).any(|w| {
+                w.windows(2)
+                    .all(|pair| match (self.rank(pair[0]), self.rank(pair[1])) {
+                        (Some(a), Some(b)) => a + 1 == b,
+                        _ => false,
+                    })
+            })
+    }
+
+    pub fn is_valid(&self, password: &[u8]) -> bool {
+        password.iter().all(|&c| self.is_allowed(c))
+            && self.rules.iter().all(|rule| match rule {
+                PasswordRule::ForbiddenLetters(_) => true,
+                PasswordRule::Straight(len) => self.has_straight(password, *len),
+                PasswordRule::DistinctPairs(count) => count_distinct_pairs(password) >= *count,
+                PasswordRule::MinLength(len) => password.len() >= *len,
+                PasswordRule::MaxLength(len) => password.len() <= *len,
+            })
+    }
+
+    fn is_satisfiable(&self) -> bool {
+        let allowed: Vec<bool> = self.alphabet.iter().map(|&c| self.is_allowed(c)).collect();
+        let allowed_count = allowed.iter().filter(|&&a| a).count();
+        let longest_straight = allowed.split(|&a| !a).map(<[bool]>::len).max().unwrap_or(0);
+
+        allowed_count > 0
+            && !matches!(self.max_length(), Some(max) if self.min_length() > max)
+            && self.rules.iter().all(|rule| match rule {
+                PasswordRule::Straight(len) => *len <= longest_straight.max(1),
+                PasswordRule::DistinctPairs(count) => *count <= allowed_count,
+                _ => true,
+            })
+    }
+
+    fn increment(&self, mut password: Vec<u8>) -> Vec<u8> {
+        let first = self.first_symbol().unwrap();
+        let carry = password
+            .iter_mut()
+            .rev()
+            .try_for_each(|c| match self.next_symbol(*c) {
+                Some(next) => {
+                    *c = next;
+                    Err(())
+                }
+                None => {
+                    *c = first;
+                    Ok(())
+                }
+            })
+            .is_ok();
+
+        if carry {
+            password.insert(0, first);
+        }
+
+        password
+    }
+
+    fn normalize(&self, mut password: Vec<u8>) -> Vec<u8> {
+        let first = self.first_symbol().unwrap();
+        if password.len() < self.min_length() {
+            return vec![first; self.min_length()];
+        }
+
+        let len = password.len();
+        if let Some(pos) = password.iter().position(|&c| !self.is_allowed(c)) {
+            password.truncate(pos + 1);
+            password = self.increment(password);
+            password.resize(len.max(password.len()), first);
+        }
+
+        password
     }
 }
 
-fn inc_password(mut password: Vec<u8>) -> Vec<u8> {
-    let mut chars_rev_iter = password.iter_mut().rev();
+pub fn next_valid_linear(password: &[u8], policy: &PasswordPolicy) -> Option<Vec<u8>> {
+    if !policy.is_satisfiable() {
+        return None;
+    }
+
+    let mut password = policy.normalize(password.to_vec());
+    while !policy.is_valid(&password) {
+        if matches!(policy.max_length(), Some(max) if password.len() > max) {
+            return None;
+        }
+        password = policy.increment(password);
+    }
+
+    Some(password)
+}
 
-    let carry = chars_rev_iter
-        .try_for_each(|c| match c {
-            b'z' => {
-                *c = b'a';
-                Ok(())
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct SearchState {
+    last: Option<usize>,
+    run: usize,
+    has_straight: bool,
+    pairs: Vec<usize>,
+    has_pairs: bool,
+}
+
+struct Search<'a> {
+    policy: &'a PasswordPolicy,
+    symbols: Vec<(u8, usize)>,
+    straight_len: usize,
+    pairs_count: usize,
+    feasible: HashMap<(usize, SearchState), bool>,
+}
+
+impl<'a> Search<'a> {
+    fn new(policy: &'a PasswordPolicy) -> Self {
+        let symbols = (policy.alphabet.iter().copied().enumerate())
+            .filter(|&(_, c)| policy.is_allowed(c))
+            .map(|(rank, c)| (c, rank))
+            .collect();
+
+        let max_rule = |pick: fn(&PasswordRule) -> Option<usize>| {
+            policy.rules.iter().filter_map(pick).max().unwrap_or(0)
+        };
+
+        Search {
+            policy,
+            symbols,
+            straight_len: max_rule(|rule| match rule {
+                PasswordRule::Straight(len) => Some(*len),
+                _ => None,
+            }),
+            pairs_count: max_rule(|rule| match rule {
+                PasswordRule::DistinctPairs(count) => Some(*count),
+                _ => None,
+            }),
+            feasible: HashMap::new(),
+        }
+    }
+
+    fn initial_state(&self) -> SearchState {
+        SearchState {
+            last: None,
+            run: 0,
+            has_straight: self.straight_len <= 1,
+            pairs: Vec::new(),
+            has_pairs: self.pairs_count == 0,
+        }
+    }
+
+    fn push(&self, state: &SearchState, rank: usize) -> SearchState {
+        let mut next = state.clone();
+        next.run = match state.last {
+            Some(last) if last + 1 == rank => state.run + 1,
+            _ => 1,
+        };
+        next.has_straight |= next.run >= self.straight_len;
+
+        if state.last == Some(rank) && !next.has_pairs && !next.pairs.contains(&rank) {
+            next.pairs.push(rank);
+            next.pairs.sort_unstable();
+            if next.pairs.len() >= self.pairs_count {
+                next.has_pairs = true;
+                next.pairs.clear();
             }
-            _ => {
-                *c = next_char(*c);
-                Err(())
+        }
+
+        next.last = Some(rank);
+        next
+    }
+
+    fn is_complete(state: &SearchState) -> bool {
+        state.has_straight && state.has_pairs
+    }
+
+    fn is_feasible(&mut self, remaining: usize, state: &SearchState) -> bool {
+        if Self::is_complete(state) {
+            return true;
+        }
+        if remaining == 0 {
+            return false;
+        }
+
+        let key = (remaining, state.clone());
+        if let Some(&feasible) = self.feasible.get(&key) {
+            return feasible;
+        }
+
+        let feasible = (0..self.symbols.len()).any(|i| {
+            let next = self.push(state, self.symbols[i].1);
+            self.is_feasible(remaining - 1, &next)
+        });
+        self.feasible.insert(key, feasible);
+        feasible
+    }
+
+    fn complete(&mut self, mut password: Vec<u8>, mut state: SearchState, len: usize) -> Vec<u8> {
+        while password.len() < len {
+            let remaining = len - password.len() - 1;
+            let candidates: Vec<(u8, SearchState)> = (self.symbols.iter())
+                .map(|&(c, rank)| (c, self.push(&state, rank)))
+                .collect();
+            let (c, next) = candidates
+                .into_iter()
+                .find(|(_, next)| self.is_feasible(remaining, next))
+                .unwrap();
+            password.push(c);
+            state = next;
+        }
+
+        password
+    }
+
+    fn rank(&self, c: u8) -> usize {
+        self.policy.rank(c).unwrap()
+    }
+
+    fn next_with_len(&mut self, password: &[u8]) -> Option<Vec<u8>> {
+        let len = password.len();
+        let mut states = vec![self.initial_state()];
+        for &c in password {
+            let next = self.push(states.last().unwrap(), self.rank(c));
+            states.push(next);
+        }
+
+        if Self::is_complete(&states[len]) {
+            return Some(password.to_vec());
+        }
+
+        for k in (0..len).rev() {
+            let rank = self.rank(password[k]);
+            for i in 0..self.symbols.len() {
+                let (c, symbol_rank) = self.symbols[i];
+                if symbol_rank <= rank {
+                    continue;
+                }
+
+                let state = self.push(&states[k], symbol_rank);
+                if self.is_feasible(len - k - 1, &state) {
+                    let mut prefix = password[..k].to_vec();
+                    prefix.push(c);
+                    return Some(self.complete(prefix, state, len));
+                }
             }
-        })
-        .is_ok();
+        }
 
-    if carry {
-        password.insert(0, b'a');
+        None
     }
 
-    password
+    fn next_valid(&mut self, password: &[u8]) -> Option<Vec<u8>> {
+        let password = self.policy.normalize(password.to_vec());
+        let max_length = self.policy.max_length().unwrap_or(usize::MAX);
+        if password.len() > max_length {
+            return None;
+        }
+        if let Some(found) = self.next_with_len(&password) {
+            return Some(found);
+        }
+
+        let initial = self.initial_state();
+        (password.len() + 1..=max_length)
+            .find(|&len| self.is_feasible(len, &initial))
+            .map(|len| self.complete(Vec::new(), initial, len))
+    }
 }
 
-fn next_secure_password(mut password: Vec<u8>) -> Vec<u8> {
-    while !is_secure_password(&password) {
-        password = inc_password(password);
+pub fn next_valid(password: &[u8], policy: &PasswordPolicy) -> Option<Vec<u8>> {
+    if !policy.is_satisfiable() {
+        return None;
     }
 
-    password
+    Search::new(policy).next_valid(password)
 }
 
-fn remove_prohibited_letters(mut password: Vec<u8>) -> Vec<u8> {
-    let len = password.len();
-    let chars_to_keep = password
-        .iter()
-        .take_while(|c| !PROHIBITED_LETTERS.contains(&c))
-        .count();
+pub fn valid_passwords<'a>(
+    password: &[u8],
+    policy: &'a PasswordPolicy,
+) -> impl Iterator<Item = Vec<u8>> + 'a {
+    let mut search = policy.is_satisfiable().then(|| Search::new(policy));
+    let first = search
+        .as_mut()
+        .and_then(|search| search.next_valid(password));
 
-    if chars_to_keep != len {
-        password.truncate(chars_to_keep + 1);
-        password = inc_password(password);
-        password.resize(len, b'a');
-    }
-
-    password
+    std::iter::successors(first, move |password| {
+        let next = policy.increment(password.clone());
+        search.as_mut().and_then(|search| search.next_valid(&next))
+    })
 }
 
-fn solve(input: impl std::io::BufRead, get_next_password: fn(Vec<u8>) -> Vec<u8>) -> String {
+fn solve(input: impl std::io::BufRead, skip: usize) -> Option<String> {
+    let policy = PasswordPolicy::default();
     let password: Vec<u8> = input.bytes().map(Result::unwrap).collect();
-    let result = get_next_password(remove_prohibited_letters(password));
-    std::str::from_utf8(&result).unwrap().to_string()
+    let password = valid_passwords(&password, &policy).nth(skip)?;
+
+    Some(String::from_utf8(password).unwrap())
 }
 
-pub fn solve_a(input: impl std::io::BufRead) -> String {
-    solve(input, next_secure_password)
+pub fn next_passwords(input: impl std::io::BufRead, count: usize, linear: bool) -> Vec<String> {
+    let policy = PasswordPolicy::default();
+    let password: Vec<u8> = input.bytes().map(Result::unwrap).collect();
+    let passwords: Box<dyn Iterator<Item = Vec<u8>>> = if linear {
+        Box::new(std::iter::successors(
+            next_valid_linear(&password, &policy),
+            |password| next_valid_linear(&policy.increment(password.clone()), &policy),
+        ))
+    } else {
+        Box::new(valid_passwords(&password, &policy))
+    };
+
+    passwords
+        .take(count)
+        .map(|password| String::from_utf8(password).unwrap())
+        .collect()
 }
 
-pub fn solve_b(input: impl std::io::BufRead) -> String {
-    solve(input, |password| {
-        let secure_password1 = next_secure_password(password);
-        let secure_password2 = next_secure_password(inc_password(secure_password1));
+pub fn solve_a(input: impl std::io::BufRead) -> Option<String> {
+    solve(input, 0)
+}
 
-        secure_password2
-    })
+pub fn solve_b(input: impl std::io::BufRead) -> Option<String> {
+    solve(input, 1)
 }
 
 #[cfg(test)]
@@ -107,37 +431,176 @@ mod tests {
 
     #[test]
     fn check_impl() {
-        assert!(!is_secure_password(&b"hijklmmn".to_vec()));
-        assert!(!is_secure_password(&b"abbceffg".to_vec()));
-        assert!(!is_secure_password(&b"abbcegjk".to_vec()));
-
-        assert!(is_secure_password(&b"abcdffaa".to_vec()));
-        assert!(is_secure_password(&b"ghjaabcc".to_vec()));
-
-        assert_eq!(b"a".to_vec(), inc_password(b"".to_vec()));
-        assert_eq!(b"b".to_vec(), inc_password(b"a".to_vec()));
-        assert_eq!(b"aa".to_vec(), inc_password(b"z".to_vec()));
-        assert_eq!(b"ab".to_vec(), inc_password(b"aa".to_vec()));
-        assert_eq!(b"zb".to_vec(), inc_password(b"za".to_vec()));
-        assert_eq!(b"ba".to_vec(), inc_password(b"az".to_vec()));
-        assert_eq!(b"aaa".to_vec(), inc_password(b"zz".to_vec()));
-
-        assert_eq!(b"".to_vec(), remove_prohibited_letters(b"".to_vec()));
-        assert_eq!(b"a".to_vec(), remove_prohibited_letters(b"a".to_vec()));
-        assert_eq!(b"m".to_vec(), remove_prohibited_letters(b"l".to_vec()));
-        assert_eq!(
-            b"paaa".to_vec(),
-            remove_prohibited_letters(b"oabc".to_vec())
-        );
-        assert_eq!(
-            b"abcjaaa".to_vec(),
-            remove_prohibited_letters(b"abcidef".to_vec())
-        );
+        let policy = PasswordPolicy::default();
+
+        assert!(!policy.is_valid(b"hijklmmn"));
+        assert!(!policy.is_valid(b"abbceffg"));
+        assert!(!policy.is_valid(b"abbcegjk"));
+
+        assert!(policy.is_valid(b"abcdffaa"));
+        assert!(policy.is_valid(b"ghjaabcc"));
+
+        let inc_password = |password: &[u8]| policy.increment(password.to_vec());
+        assert_eq!(b"a".to_vec(), inc_password(b""));
+        assert_eq!(b"b".to_vec(), inc_password(b"a"));
+        assert_eq!(b"aa".to_vec(), inc_password(b"z"));
+        assert_eq!(b"ab".to_vec(), inc_password(b"aa"));
+        assert_eq!(b"zb".to_vec(), inc_password(b"za"));
+        assert_eq!(b"ba".to_vec(), inc_password(b"az"));
+        assert_eq!(b"aaa".to_vec(), inc_password(b"zz"));
+        assert_eq!(b"j".to_vec(), inc_password(b"h"));
+
+        let remove_prohibited_letters = |password: &[u8]| policy.normalize(password.to_vec());
+        assert_eq!(b"".to_vec(), remove_prohibited_letters(b""));
+        assert_eq!(b"a".to_vec(), remove_prohibited_letters(b"a"));
+        assert_eq!(b"m".to_vec(), remove_prohibited_letters(b"l"));
+        assert_eq!(b"paaa".to_vec(), remove_prohibited_letters(b"oabc"));
+        assert_eq!(b"abcjaaa".to_vec(), remove_prohibited_letters(b"abcidef"));
     }
 
     #[test]
     fn check_a() {
-        assert_eq!("abcdffaa", solve_a(&b"abcdefgh"[..]));
-        assert_eq!("ghjaabcc", solve_a(&b"ghijklmn"[..]));
+        assert_eq!(Some(String::from("abcdffaa")), solve_a(&b"abcdefgh"[..]));
+        assert_eq!(Some(String::from("ghjaabcc")), solve_a(&b"ghijklmn"[..]));
+    }
+
+    #[test]
+    fn check_policies() {
+        let next = |password: &[u8], policy: &PasswordPolicy| {
+            next_valid(password, policy).map(|p| String::from_utf8(p).unwrap())
+        };
+
+        let policy = PasswordPolicy::new(b"abc");
+        assert_eq!(Some(String::from("ab")), next(b"ab", &policy));
+
+        let policy = PasswordPolicy::new(b"abc").with_rule(PasswordRule::Straight(3));
+        assert_eq!(Some(String::from("abc")), next(b"a", &policy));
+        assert_eq!(Some(String::from("aabc")), next(b"cca", &policy));
+
+        let policy = PasswordPolicy::new(b"0123456789")
+            .with_rule(PasswordRule::ForbiddenLetters(b"5".to_vec()))
+            .with_rule(PasswordRule::DistinctPairs(2))
+            .with_rule(PasswordRule::MinLength(4));
+        assert_eq!(Some(String::from("0011")), next(b"9", &policy));
+        assert_eq!(Some(String::from("6600")), next(b"5000", &policy));
+        assert_eq!(Some(String::from("1100")), next(b"0999", &policy));
+
+        let policy = PasswordPolicy::new(b"xyz")
+            .with_rule(PasswordRule::DistinctPairs(1))
+            .with_rule(PasswordRule::MaxLength(2));
+        assert_eq!(Some(String::from("zz")), next(b"yz", &policy));
+        assert_eq!(None, next(b"zzx", &policy));
+
+        let policy =
+            PasswordPolicy::new(b"ab").with_rule(PasswordRule::ForbiddenLetters(b"b".to_vec()));
+        assert_eq!(Some(String::from("aa")), next(b"b", &policy));
+
+        let policy = PasswordPolicy::default().with_rule(PasswordRule::Straight(12));
+        assert_eq!(None, next(b"abc", &policy));
+        let policy = PasswordPolicy::default().with_rule(PasswordRule::DistinctPairs(24));
+        assert_eq!(None, next(b"abc", &policy));
+        let policy = PasswordPolicy::new(b"abc")
+            .with_rule(PasswordRule::MinLength(3))
+            .with_rule(PasswordRule::MaxLength(2));
+        assert_eq!(None, next(b"abc", &policy));
+    }
+
+    #[test]
+    fn check_skip_ahead() {
+        let short_passwords: Vec<&[u8]> = vec![
+            b"", b"a", b"ab", b"cca", b"zzx", b"xyzzy", b"9", b"0999", b"5000", b"fedcba",
+            b"ffffff", b"eaeaea", b"aabb",
+        ];
+        let cases = vec![
+            (
+                PasswordPolicy::default(),
+                vec![&b"abcdefgh"[..], b"zzzzzzzy"],
+            ),
+            (
+                PasswordPolicy::new(b"abc").with_rule(PasswordRule::Straight(3)),
+                short_passwords.clone(),
+            ),
+            (
+                PasswordPolicy::new(b"0123456789")
+                    .with_rule(PasswordRule::ForbiddenLetters(b"5".to_vec()))
+                    .with_rule(PasswordRule::DistinctPairs(2))
+                    .with_rule(PasswordRule::MinLength(4)),
+                short_passwords.clone(),
+            ),
+            (
+                PasswordPolicy::new(b"xyz")
+                    .with_rule(PasswordRule::DistinctPairs(1))
+                    .with_rule(PasswordRule::MaxLength(2)),
+                short_passwords.clone(),
+            ),
+            (
+                PasswordPolicy::new(b"abcdef")
+                    .with_rule(PasswordRule::ForbiddenLetters(b"c".to_vec()))
+                    .with_rule(PasswordRule::Straight(2))
+                    .with_rule(PasswordRule::DistinctPairs(2))
+                    .with_rule(PasswordRule::MaxLength(6)),
+                short_passwords,
+            ),
+        ];
+
+        for (policy, passwords) in &cases {
+            for password in passwords {
+                if !password.iter().all(|&c| policy.rank(c).is_some()) {
+                    continue;
+                }
+
+                let expected = next_valid_linear(password, policy);
+                assert_eq!(expected, next_valid(password, policy), "{:?}", password);
+
+                let linear = std::iter::successors(expected, |password| {
+                    next_valid_linear(&policy.increment(password.clone()), policy)
+                });
+                assert!(linear.take(5).eq(valid_passwords(password, policy).take(5)));
+            }
+        }
+
+        let policy = PasswordPolicy::default();
+        let next: Vec<String> = valid_passwords(b"abcdefgh", &policy)
+            .take(3)
+            .map(|p| String::from_utf8(p).unwrap())
+            .collect();
+        assert_eq!(vec!["abcdffaa", "abcdffbb", "abcdffcc"], next);
+    }
+
+    #[test]
+    fn check_skip_ahead_work() {
+        // Position of a password in the order `increment` walks through candidates.
+        let linear_index = |policy: &PasswordPolicy, password: &[u8]| {
+            let symbols: Vec<u8> = (policy.alphabet.iter().copied())
+                .filter(|&c| policy.is_allowed(c))
+                .collect();
+            let base = symbols.len() as u128;
+            let shorter: u128 = (0..password.len() as u32).map(|len| base.pow(len)).sum();
+            password.iter().fold(shorter, |index, c| {
+                index * base + symbols.iter().position(|s| s == c).unwrap() as u128
+            })
+        };
+
+        let policy = PasswordPolicy::default();
+        let mut search = Search::new(&policy);
+        let found = search.next_valid(b"hepxcrrq").unwrap();
+        assert_eq!(b"hepxxyzz".to_vec(), found);
+        let steps = linear_index(&policy, &found) - linear_index(&policy, b"hepxcrrq");
+        assert_eq!(Some(found), next_valid_linear(b"hepxcrrq", &policy));
+        assert!(steps > 100_000, "{}", steps);
+        assert!(search.feasible.len() < 1_000, "{}", search.feasible.len());
+
+        // No 8-letter password can hold a 5-letter straight and three pairs,
+        // so the linear scan would have to exhaust every 8-letter candidate.
+        let policy = PasswordPolicy::default()
+            .with_rule(PasswordRule::Straight(5))
+            .with_rule(PasswordRule::DistinctPairs(3));
+        let mut search = Search::new(&policy);
+        let found = search.next_valid(b"zzzzzzzy").unwrap();
+        assert_eq!(b"aabbcdeff".to_vec(), found);
+        assert!(policy.is_valid(&found));
+        let steps = linear_index(&policy, &found) - linear_index(&policy, b"zzzzzzzy");
+        assert!(steps > 100_000_000, "{}", steps);
+        assert!(search.feasible.len() < 100_000, "{}", search.feasible.len());
     }
 }
@@ -1,71 +1,587 @@
-use serde_json::value::Value;
-use serde_json::Value as JsonValue;
+use crate::utils::decimal::Decimal;
+use crate::utils::parsing::parse_decimal;
 
-fn sum_numbers_a(json_val: &JsonValue) -> i64 {
-    match json_val {
-        Value::Null => 0,
-        Value::Bool(_) => 0,
-        Value::Number(n) => n.as_i64().unwrap(),
-        Value::String(_) => 0,
-        Value::Array(arr) => arr.iter().map(sum_numbers_a).sum(),
-        Value::Object(obj_map) => obj_map.values().map(sum_numbers_a).sum(),
+type NomResult<'a, T> = nom::IResult<&'a str, T>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    BeginObject,
+    EndObject,
+    BeginArray,
+    EndArray,
+    Colon,
+    Comma,
+    String(String),
+    Number(String),
+    Literal(String),
+}
+
+struct Tokenizer<I: Iterator<Item = u8>> {
+    bytes: std::iter::Peekable<I>,
+}
+
+fn tokenize(input: impl std::io::BufRead) -> Tokenizer<impl Iterator<Item = u8>> {
+    Tokenizer {
+        bytes: input.bytes().map(Result::unwrap).peekable(),
+    }
+}
+
+impl<I: Iterator<Item = u8>> Tokenizer<I> {
+    fn next_byte(&mut self) -> u8 {
+        self.bytes.next().expect("Unexpected end of JSON")
+    }
+
+    fn take_while(&mut self, first: u8, pred: fn(u8) -> bool) -> String {
+        let mut text = vec![first];
+        while let Some(&c) = self.bytes.peek() {
+            if !pred(c) {
+                break;
+            }
+            text.push(c);
+            self.bytes.next();
+        }
+
+        String::from_utf8(text).unwrap()
+    }
+
+    fn read_hex_char(&mut self) -> u32 {
+        let hex: Vec<u8> = (0..4).map(|_| self.next_byte()).collect();
+        let hex = std::str::from_utf8(&hex).expect("Invalid unicode escape");
+        u32::from_str_radix(hex, 16).expect("Invalid unicode escape")
+    }
+
+    fn read_string(&mut self) -> String {
+        let mut text = Vec::new();
+        loop {
+            match self.next_byte() {
+                b'"' => break,
+                b'\\' => match self.next_byte() {
+                    b'"' => text.push(b'"'),
+                    b'\\' => text.push(b'\\'),
+                    b'/' => text.push(b'/'),
+                    b'b' => text.push(0x08),
+                    b'f' => text.push(0x0c),
+                    b'n' => text.push(b'\n'),
+                    b'r' => text.push(b'\r'),
+                    b't' => text.push(b'\t'),
+                    b'u' => {
+                        let c = std::char::from_u32(self.read_hex_char()).unwrap_or('\u{fffd}');
+                        text.extend_from_slice(c.to_string().as_bytes());
+                    }
+                    c => panic!("Invalid escape: \\{}", c as char),
+                },
+                c => text.push(c),
+            }
+        }
+
+        String::from_utf8(text).expect("Invalid UTF-8 in JSON string")
+    }
+}
+
+impl<I: Iterator<Item = u8>> Iterator for Tokenizer<I> {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Token> {
+        let c = loop {
+            match self.bytes.next()? {
+                c if c.is_ascii_whitespace() => continue,
+                c => break c,
+            }
+        };
+
+        let token = match c {
+            b'{' => Token::BeginObject,
+            b'}' => Token::EndObject,
+            b'[' => Token::BeginArray,
+            b']' => Token::EndArray,
+            b':' => Token::Colon,
+            b',' => Token::Comma,
+            b'"' => Token::String(self.read_string()),
+            b'-' | b'0'..=b'9' => Token::Number(self.take_while(c, |c| {
+                c.is_ascii_digit() || c == b'.' || c == b'e' || c == b'E' || c == b'+' || c == b'-'
+            })),
+            b'a'..=b'z' => Token::Literal(self.take_while(c, |c| c.is_ascii_lowercase())),
+            _ => panic!("Unexpected character in JSON: {}", c as char),
+        };
+
+        Some(token)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum SelectorSegment {
+    Key(String),
+    Index(usize),
+    Wildcard,
+    Descendants,
+}
+
+type Selector = Vec<SelectorSegment>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Rule {
+    IgnoreObjectsWithValue(Token),
+    IgnoreArraysWithValue(Token),
+    OnlyUnder(Selector),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Rules(Vec<Rule>);
+
+const PART_A_RULES: &str = "";
+const PART_B_RULES: &str = "object-value=red";
+
+fn parse_selector(input: &str) -> Selector {
+    use nom::{
+        branch::alt,
+        bytes::complete::{tag, take_while1},
+        character::complete::char,
+        combinator::{all_consuming, map},
+        multi::many0,
+        sequence::{delimited, preceded},
+    };
+
+    fn parse_key(input: &str) -> NomResult<'_, SelectorSegment> {
+        let key = take_while1(is_plain_key_char);
+        alt((
+            map(char('*'), |_| SelectorSegment::Wildcard),
+            map(key, |key: &str| SelectorSegment::Key(key.to_string())),
+        ))(input)
+    }
+
+    fn parse_index(input: &str) -> NomResult<'_, SelectorSegment> {
+        let index = alt((
+            map(char('*'), |_| SelectorSegment::Wildcard),
+            map(parse_decimal, SelectorSegment::Index),
+        ));
+        delimited(char('['), index, char(']'))(input)
+    }
+
+    fn parse_segment(input: &str) -> NomResult<'_, SelectorSegment> {
+        alt((
+            map(tag(".."), |_| SelectorSegment::Descendants),
+            preceded(char('.'), parse_key),
+            parse_index,
+            parse_key,
+        ))(input)
+    }
+
+    match all_consuming(preceded(char('$'), many0(parse_segment)))(input) {
+        Ok((_, selector)) => selector,
+        Err(e) => panic!("Failed to parse selector: {:?}", e),
+    }
+}
+
+fn parse_scalar(input: &str) -> Token {
+    match input.as_bytes().first() {
+        Some(b'"') | Some(b'-') | Some(b'0'..=b'9') => {
+            let mut tokens = tokenize(input.as_bytes());
+            match (tokens.next(), tokens.next()) {
+                (Some(token @ Token::String(_)), None) | (Some(token @ Token::Number(_)), None) => {
+                    token
+                }
+                _ => panic!("Failed to parse value: {}", input),
+            }
+        }
+        _ if ["true", "false", "null"].contains(&input) => Token::Literal(input.to_string()),
+        _ => Token::String(input.to_string()),
+    }
+}
+
+impl Rules {
+    pub fn parse(input: &str) -> Self {
+        let rules = input
+            .split(';')
+            .map(str::trim)
+            .filter(|rule| !rule.is_empty())
+            .map(|rule| {
+                let (name, value) = rule
+                    .split_once('=')
+                    .unwrap_or_else(|| panic!("Failed to parse rule: {}", rule));
+
+                match name.trim() {
+                    "object-value" => Rule::IgnoreObjectsWithValue(parse_scalar(value.trim())),
+                    "array-value" => Rule::IgnoreArraysWithValue(parse_scalar(value.trim())),
+                    "path" => Rule::OnlyUnder(parse_selector(value.trim())),
+                    _ => panic!("Unknown rule: {}", name),
+                }
+            })
+            .collect();
+
+        Rules(rules)
+    }
+
+    fn ignores(&self, is_object: bool, value: &Token) -> bool {
+        self.0.iter().any(|rule| match rule {
+            Rule::IgnoreObjectsWithValue(v) => is_object && v == value,
+            Rule::IgnoreArraysWithValue(v) => !is_object && v == value,
+            Rule::OnlyUnder(_) => false,
+        })
+    }
+
+    fn selectors(&self) -> impl Iterator<Item = &Selector> {
+        self.0.iter().filter_map(|rule| match rule {
+            Rule::OnlyUnder(selector) => Some(selector),
+            _ => None,
+        })
     }
 }
 
-fn has_red(json_val: &JsonValue) -> bool {
-    match json_val {
-        Value::String(s) if s == "red" => true,
+fn segment_matches(selector_segment: &SelectorSegment, path_segment: &PathSegment) -> bool {
+    match (selector_segment, path_segment) {
+        (SelectorSegment::Wildcard, _) => true,
+        (SelectorSegment::Key(k1), PathSegment::Key(k2)) => k1 == k2,
+        (SelectorSegment::Index(i1), PathSegment::Index(i2)) => i1 == i2,
         _ => false,
     }
 }
 
-fn sum_numbers_b(json_val: &JsonValue) -> i64 {
-    match json_val {
-        Value::Array(arr) => arr.iter().map(sum_numbers_b).sum(),
-        Value::Object(obj_map) if obj_map.values().any(has_red) => 0,
-        Value::Object(obj_map) => obj_map.values().map(sum_numbers_b).sum(),
-        _ => sum_numbers_a(json_val),
+fn selector_matches_prefix(selector: &[SelectorSegment], path: &[PathSegment]) -> bool {
+    match selector {
+        [] => true,
+        [SelectorSegment::Descendants, rest @ ..] => {
+            (0..=path.len()).any(|skip| selector_matches_prefix(rest, &path[skip..]))
+        }
+        [segment, rest @ ..] => match path {
+            [] => false,
+            [path_segment, path_rest @ ..] => {
+                segment_matches(segment, path_segment) && selector_matches_prefix(rest, path_rest)
+            }
+        },
+    }
+}
+
+impl std::fmt::Display for PathSegment {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            PathSegment::Key(key) if !key.is_empty() && key.chars().all(is_plain_key_char) => {
+                write!(f, ".{}", key)
+            }
+            PathSegment::Key(key) => write!(f, "[{:?}]", key),
+            PathSegment::Index(index) => write!(f, "[{}]", index),
+        }
     }
 }
 
-pub fn solve(mut input: impl std::io::BufRead, sum_numbers: fn(&JsonValue) -> i64) -> i64 {
-    let mut input_str = String::new();
-    let bytes_read = input.read_line(&mut input_str).unwrap();
-    assert!(bytes_read > 0);
+fn is_plain_key_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-'
+}
+
+fn format_path(path: &[PathSegment]) -> String {
+    path.iter().fold(String::from("$"), |mut text, segment| {
+        text.push_str(&segment.to_string());
+        text
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Subtotal {
+    pub path: String,
+    pub sum: Decimal,
+    pub ignored: bool,
+}
+
+impl std::fmt::Display for Subtotal {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} = {}", self.path, self.sum)?;
+        if self.ignored {
+            write!(f, " (ignored)")?;
+        }
+        Ok(())
+    }
+}
+
+struct Frame {
+    is_object: bool,
+    expects_key: bool,
+    ignored: bool,
+    sum: Decimal,
+    key: Option<String>,
+    index: usize,
+    subtotal: Option<usize>,
+}
+
+impl Frame {
+    fn new(is_object: bool) -> Self {
+        Frame {
+            is_object,
+            expects_key: is_object,
+            ignored: false,
+            sum: Decimal::zero(),
+            key: None,
+            index: 0,
+            subtotal: None,
+        }
+    }
+
+    fn path_segment(&self) -> PathSegment {
+        match &self.key {
+            Some(key) if self.is_object => PathSegment::Key(key.clone()),
+            _ => PathSegment::Index(self.index),
+        }
+    }
+}
+
+fn current_path(stack: &[Frame]) -> Vec<PathSegment> {
+    stack[1..].iter().map(Frame::path_segment).collect()
+}
+
+fn is_selected(stack: &[Frame], rules: &Rules) -> bool {
+    let mut selectors = rules.selectors().peekable();
+    if selectors.peek().is_none() {
+        return true;
+    }
+
+    let path = current_path(stack);
+    selectors.any(|selector| selector_matches_prefix(selector, &path))
+}
+
+fn sum_numbers(
+    tokens: impl Iterator<Item = Token>,
+    rules: &Rules,
+    max_depth: Option<usize>,
+) -> (Decimal, Vec<Subtotal>) {
+    let mut stack = vec![Frame::new(false)];
+    let mut subtotals = Vec::new();
+
+    for token in tokens {
+        match token {
+            Token::BeginObject | Token::BeginArray => {
+                let mut frame = Frame::new(token == Token::BeginObject);
+                if matches!(max_depth, Some(depth) if stack.len() <= depth + 1) {
+                    frame.subtotal = Some(subtotals.len());
+                    subtotals.push(Subtotal {
+                        path: format_path(&current_path(&stack)),
+                        sum: Decimal::zero(),
+                        ignored: false,
+                    });
+                }
+                stack.push(frame);
+            }
+            Token::EndObject | Token::EndArray => {
+                let frame = stack.pop().unwrap();
+                let parent = stack.last_mut().expect("Unbalanced JSON");
+                if let Some(slot) = frame.subtotal {
+                    subtotals[slot].sum = frame.sum.clone();
+                    subtotals[slot].ignored = frame.ignored;
+                }
+                if !frame.ignored {
+                    parent.sum += frame.sum;
+                }
+            }
+            Token::Colon => stack.last_mut().unwrap().expects_key = false,
+            Token::Comma => {
+                let frame = stack.last_mut().unwrap();
+                frame.expects_key = frame.is_object;
+                frame.index += 1;
+            }
+            Token::String(key) if stack.last().unwrap().expects_key => {
+                stack.last_mut().unwrap().key = Some(key);
+            }
+            value => {
+                let number = match &value {
+                    Token::Number(n) if is_selected(&stack, rules) => {
+                        n.parse::<Decimal>().unwrap_or_else(|e| panic!("{}", e))
+                    }
+                    _ => Decimal::zero(),
+                };
+
+                let frame = stack.last_mut().unwrap();
+                frame.sum += number;
+                if rules.ignores(frame.is_object, &value) {
+                    frame.ignored = true;
+                }
+            }
+        }
+    }
+
+    assert!(stack.len() == 1, "Unbalanced JSON");
+    (stack.pop().unwrap().sum, subtotals)
+}
+
+pub fn solve_with_rules(input: impl std::io::BufRead, rules: &Rules) -> Decimal {
+    sum_numbers(tokenize(input), rules, None).0
+}
 
-    let json_val: JsonValue = serde_json::from_str(&input_str).unwrap();
-    sum_numbers(&json_val)
+pub fn subtotals(
+    input: impl std::io::BufRead,
+    rules: &Rules,
+    max_depth: usize,
+) -> (Decimal, Vec<Subtotal>) {
+    sum_numbers(tokenize(input), rules, Some(max_depth))
 }
 
-pub fn solve_a(input: impl std::io::BufRead) -> i64 {
-    solve(input, sum_numbers_a)
+pub fn solve_a(input: impl std::io::BufRead) -> Decimal {
+    solve_with_rules(input, &Rules::parse(PART_A_RULES))
 }
 
-pub fn solve_b(input: impl std::io::BufRead) -> i64 {
-    solve(input, sum_numbers_b)
+pub fn solve_b(input: impl std::io::BufRead) -> Decimal {
+    solve_with_rules(input, &Rules::parse(PART_B_RULES))
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
 
+    #[test]
+    fn check_tokenizer() {
+        let tokens: Vec<Token> = tokenize(&br#"{"a\"A": [-1.5e3, true, null]}"#[..]).collect();
+        assert_eq!(
+            vec![
+                Token::BeginObject,
+                Token::String(String::from("a\"A")),
+                Token::Colon,
+                Token::BeginArray,
+                Token::Number(String::from("-1.5e3")),
+                Token::Comma,
+                Token::Literal(String::from("true")),
+                Token::Comma,
+                Token::Literal(String::from("null")),
+                Token::EndArray,
+                Token::EndObject,
+            ],
+            tokens
+        );
+    }
+
     #[test]
     fn check_a() {
-        assert_eq!(6, solve_a(&br#"[1,2,3]"#[..]));
-        assert_eq!(6, solve_a(&br#"{"a":2,"b":4}"#[..]));
-        assert_eq!(3, solve_a(&br#"[[[3]]]"#[..]));
-        assert_eq!(3, solve_a(&br#"{"a":{"b":4},"c":-1}"#[..]));
-        assert_eq!(0, solve_a(&br#"{"a":[-1,1]}"#[..]));
-        assert_eq!(0, solve_a(&br#"[-1,{"a":1}]"#[..]));
-        assert_eq!(0, solve_a(&br#"[]"#[..]));
-        assert_eq!(0, solve_a(&br#"{}"#[..]));
+        assert_eq!(Decimal::from(6), solve_a(&br#"[1,2,3]"#[..]));
+        assert_eq!(Decimal::from(6), solve_a(&br#"{"a":2,"b":4}"#[..]));
+        assert_eq!(Decimal::from(3), solve_a(&br#"[[[3]]]"#[..]));
+        assert_eq!(Decimal::from(3), solve_a(&br#"{"a":{"b":4},"c":-1}"#[..]));
+        assert_eq!(Decimal::from(0), solve_a(&br#"{"a":[-1,1]}"#[..]));
+        assert_eq!(Decimal::from(0), solve_a(&br#"[-1,{"a":1}]"#[..]));
+        assert_eq!(Decimal::from(0), solve_a(&br#"[]"#[..]));
+        assert_eq!(Decimal::from(0), solve_a(&br#"{}"#[..]));
     }
 
     #[test]
     fn check_b() {
-        assert_eq!(6, solve_b(&br#"[1,2,3]"#[..]));
-        assert_eq!(4, solve_b(&br#"[1,{"c":"red","b":2},3]"#[..]));
-        assert_eq!(0, solve_b(&br#"{"d":"red","e":[1,2,3,4],"f":5}"#[..]));
-        assert_eq!(6, solve_b(&br#"[1,"red",5]"#[..]));
+        assert_eq!(Decimal::from(6), solve_b(&br#"[1,2,3]"#[..]));
+        assert_eq!(
+            Decimal::from(4),
+            solve_b(&br#"[1,{"c":"red","b":2},3]"#[..])
+        );
+        assert_eq!(
+            Decimal::from(0),
+            solve_b(&br#"{"d":"red","e":[1,2,3,4],"f":5}"#[..])
+        );
+        assert_eq!(Decimal::from(6), solve_b(&br#"[1,"red",5]"#[..]));
+        assert_eq!(Decimal::from(5), solve_b(&br#"{"red":5}"#[..]));
+    }
+
+    #[test]
+    fn check_multiline() {
+        let input = b"{\n  \"a\": [\n    1,\n    2\n  ],\n  \"b\": {\n    \"c\": \"red\",\n    \"d\": 10\n  }\n}\n";
+        assert_eq!(Decimal::from(13), solve_a(&input[..]));
+        assert_eq!(Decimal::from(3), solve_b(&input[..]));
+    }
+
+    #[test]
+    fn check_selectors() {
+        use PathSegment::{Index, Key};
+
+        assert_eq!(
+            vec![
+                SelectorSegment::Key(String::from("a")),
+                SelectorSegment::Descendants,
+                SelectorSegment::Key(String::from("b")),
+                SelectorSegment::Index(2),
+                SelectorSegment::Wildcard,
+                SelectorSegment::Wildcard,
+            ],
+            parse_selector("$.a..b[2][*].*")
+        );
+
+        let path = vec![Key(String::from("a")), Index(1), Key(String::from("b"))];
+        assert!(selector_matches_prefix(&parse_selector("$"), &path));
+        assert!(selector_matches_prefix(&parse_selector("$.a"), &path));
+        assert!(selector_matches_prefix(&parse_selector("$.a[1].b"), &path));
+        assert!(selector_matches_prefix(&parse_selector("$.*[*]"), &path));
+        assert!(selector_matches_prefix(&parse_selector("$..b"), &path));
+        assert!(!selector_matches_prefix(&parse_selector("$.b"), &path));
+        assert!(!selector_matches_prefix(&parse_selector("$.a[0]"), &path));
+        assert!(!selector_matches_prefix(
+            &parse_selector("$.a[1].b.c"),
+            &path
+        ));
+    }
+
+    #[test]
+    fn check_rules() {
+        let input = br#"{"a":[1,{"x":"blue","y":10}],"b":{"x":"red","y":100},"c":[1000,"red"],"d":{"x":5,"y":10000}}"#;
+        let solve = |rules: &str| solve_with_rules(&input[..], &Rules::parse(rules));
+
+        assert_eq!(Decimal::from(11116), solve(""));
+        assert_eq!(Decimal::from(11016), solve("object-value=red"));
+        assert_eq!(Decimal::from(10116), solve("array-value=red"));
+        assert_eq!(
+            Decimal::from(10016),
+            solve("object-value=red; array-value=\"red\"")
+        );
+        assert_eq!(Decimal::from(11106), solve("object-value=blue"));
+        assert_eq!(Decimal::from(1111), solve("object-value=5"));
+        assert_eq!(Decimal::from(11), solve("path=$.a"));
+        assert_eq!(Decimal::from(10110), solve("path=$..y"));
+        assert_eq!(Decimal::from(10010), solve("path=$..y; object-value=red"));
+        assert_eq!(Decimal::from(1001), solve("path=$.a[0]; path=$.c"));
+        assert_eq!(Decimal::from(10100), solve("path=$.*.y"));
+
+        assert_eq!(Rules::default(), Rules::parse(PART_A_RULES));
+    }
+
+    #[test]
+    fn check_exact_numbers() {
+        let solve = |input: &str| solve_a(input.as_bytes()).to_string();
+
+        assert_eq!("0.3", solve("[0.1, 0.2]"));
+        assert_eq!("1499.75", solve(r#"{"a": 1.5e3, "b": [-0.25]}"#));
+        assert_eq!("0", solve("[1e-30, -1E-30]"));
+        assert_eq!(
+            "18446744073709551616",
+            solve("[9223372036854775808, 9223372036854775808]")
+        );
+        assert_eq!(
+            "-100000000000000000000000000000.5",
+            solve("[-99999999999999999999999999999.5, -1]")
+        );
+    }
+
+    #[test]
+    fn check_subtotals() {
+        let input = br#"{"a":[1,{"x":"red","y":2.5}],"b":{"c":[3]},"d e":4}"#;
+        let rules = Rules::parse(PART_B_RULES);
+        let format = |subtotals: Vec<Subtotal>| -> Vec<String> {
+            subtotals.iter().map(Subtotal::to_string).collect()
+        };
+
+        let (total, lines) = subtotals(&input[..], &rules, 1);
+        let lines = format(lines);
+
+        assert_eq!("8", total.to_string());
+        assert_eq!(vec!["$ = 8", "$.a = 1", "$.b = 3"], lines);
+
+        let (_, lines) = subtotals(&input[..], &rules, 2);
+        assert_eq!(
+            vec![
+                "$ = 8",
+                "$.a = 1",
+                "$.a[1] = 2.5 (ignored)",
+                "$.b = 3",
+                "$.b.c = 3"
+            ],
+            format(lines)
+        );
+
+        let (_, lines) = subtotals(&br#"[{"d e":[7]}]"#[..], &Rules::default(), 5);
+        let paths: Vec<&str> = lines.iter().map(|s| s.path.as_str()).collect();
+        assert_eq!(vec!["$", "$[0]", r#"$[0]["d e"]"#], paths);
     }
 }
@@ -1,13 +1,15 @@
+use crate::utils::graph::{
+    best_hamiltonian_cycle, DenseGraph, NodeId, NodeInterner, WeightedGraph,
+};
 use crate::utils::parsing::{parse_decimal, parse_str_alpha, parse_ws};
-use itertools::Itertools;
 use nom::{branch::alt, bytes::complete::tag, combinator::map};
 
 type Name = String;
 type Happiness = isize;
 type NomResult<'a, T> = nom::IResult<&'a str, T>;
-type SeatsMap<'a> = std::collections::HashMap<(&'a Name, &'a Name), Happiness>;
+type HappinessGraph = DenseGraph<Happiness>;
 
-static ME: String = String::new();
+static ME: &str = "";
 
 #[derive(Debug, Eq, PartialEq)]
 struct Seat {
@@ -55,68 +57,247 @@ fn parse_seat(input: &str) -> Seat {
     }
 }
 
-fn make_seat_map(seats: &[Seat]) -> SeatsMap {
-    seats
-        .iter()
-        .map(|seat| ((&seat.name, &seat.neighbour), seat.happiness))
-        .collect()
+#[derive(Debug, Eq, PartialEq)]
+pub struct Seating {
+    pub guests: Vec<(Name, Happiness)>,
+    pub total: Happiness,
 }
 
-fn calc_happiness(seats_map: &SeatsMap, names: &[&Name]) -> Happiness {
-    let neighbours = names
-        .iter()
-        .cycle()
-        .tuple_windows::<(_, _, _)>()
-        .take(names.len());
+#[derive(Debug, Clone, Default)]
+pub struct ExtraGuest {
+    pub name: Name,
+    pub happiness: Vec<(Name, Happiness)>,
+    pub others_happiness: Vec<(Name, Happiness)>,
+}
 
-    neighbours
-        .map(|(&left, &mid, &right)| {
-            seats_map.get(&(mid, left)).unwrap() + seats_map.get(&(mid, right)).unwrap()
-        })
-        .sum()
+impl ExtraGuest {
+    pub fn neutral(name: &str) -> Self {
+        ExtraGuest {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
 }
 
-fn solve(input: impl std::io::BufRead, add_me: bool) -> Happiness {
-    let seats: Vec<Seat> = input
-        .lines()
-        .map(|line| parse_seat(&line.unwrap()))
-        .collect();
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Constraint {
+    NextTo(Name, Name),
+    NotNextTo(Name, Name),
+}
 
-    let mut names: std::collections::HashSet<Name> =
-        seats.iter().map(|seat| seat.name.clone()).collect();
+#[derive(Debug, Clone, Default)]
+pub struct SeatingOptions {
+    pub extra_guests: Vec<ExtraGuest>,
+    pub constraints: Vec<Constraint>,
+}
 
-    let mut seats_map = make_seat_map(&seats);
+#[derive(Debug, PartialEq, Eq)]
+pub enum SeatingError {
+    UnknownGuest(Name),
+    Unsatisfiable,
+}
 
-    if add_me {
-        names.insert(ME.clone());
-        for name in names.iter() {
-            seats_map.insert((&ME, name), 0);
-            seats_map.insert((name, &ME), 0);
+impl std::fmt::Display for SeatingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SeatingError::UnknownGuest(name) => write!(f, "Unknown guest: {}", name),
+            SeatingError::Unsatisfiable => write!(f, "Seating not found"),
         }
     }
+}
+
+fn get_guest(names: &NodeInterner, name: &str) -> Result<NodeId, SeatingError> {
+    names
+        .get(name)
+        .ok_or_else(|| SeatingError::UnknownGuest(name.to_string()))
+}
+
+fn make_preferences_graph(
+    seats: &[Seat],
+    extra_guests: &[ExtraGuest],
+) -> Result<(NodeInterner, HappinessGraph), SeatingError> {
+    let mut names = NodeInterner::new();
+    let preferences: Vec<_> = seats
+        .iter()
+        .map(|seat| {
+            let name = names.intern(&seat.name);
+            let neighbour = names.intern(&seat.neighbour);
+            (name, neighbour, seat.happiness)
+        })
+        .collect();
 
-    let max_happiness = names
+    let extra_ids: Vec<_> = extra_guests
         .iter()
-        .permutations(names.len())
-        .map(|names_permutatitions| calc_happiness(&seats_map, &names_permutatitions))
-        .max()
-        .unwrap();
+        .map(|guest| names.intern(&guest.name))
+        .collect();
+
+    let mut graph = DenseGraph::new(names.len());
+    for (name, neighbour, happiness) in preferences {
+        graph.add_edge(name, neighbour, happiness);
+    }
+
+    for &guest in extra_ids.iter() {
+        for other in (0..names.len()).filter(|&other| other != guest) {
+            if graph.weight(guest, other).is_none() {
+                graph.add_edge(guest, other, 0);
+            }
+            if graph.weight(other, guest).is_none() {
+                graph.add_edge(other, guest, 0);
+            }
+        }
+    }
+
+    for (guest, &id) in extra_guests.iter().zip(extra_ids.iter()) {
+        for (other, happiness) in guest.happiness.iter() {
+            graph.add_edge(id, get_guest(&names, other)?, *happiness);
+        }
+        for (other, happiness) in guest.others_happiness.iter() {
+            graph.add_edge(get_guest(&names, other)?, id, *happiness);
+        }
+    }
+
+    Ok((names, graph))
+}
+
+fn make_pairs_graph(preferences: &HappinessGraph) -> HappinessGraph {
+    let n = preferences.node_count();
+    let mut graph = DenseGraph::new(n);
+    for name in 0..n {
+        for neighbour in 0..n {
+            let happiness = preferences
+                .weight(name, neighbour)
+                .zip(preferences.weight(neighbour, name));
+
+            if let Some((h1, h2)) = happiness {
+                graph.add_edge(name, neighbour, h1 + h2);
+            }
+        }
+    }
+
+    graph
+}
+
+fn apply_constraints(
+    mut pairs: HappinessGraph,
+    names: &NodeInterner,
+    constraints: &[Constraint],
+) -> Result<(HappinessGraph, Vec<(NodeId, NodeId)>), SeatingError> {
+    let n = pairs.node_count();
+    // a bonus larger than any possible seating total makes every cycle that keeps more of the
+    // required pairs together beat every cycle that keeps fewer of them
+    let bonus: Happiness = 1
+        + (0..n)
+            .flat_map(|a| (0..n).map(move |b| (a, b)))
+            .filter_map(|(a, b)| pairs.weight(a, b))
+            .map(|happiness| happiness.abs())
+            .sum::<Happiness>();
+
+    let mut required = Vec::new();
+    for constraint in constraints {
+        match constraint {
+            Constraint::NextTo(a, b) => {
+                let (a, b) = (get_guest(names, a)?, get_guest(names, b)?);
+                if let Some(happiness) = pairs.weight(a, b) {
+                    pairs.add_undirected_edge(a, b, happiness + bonus);
+                }
+                required.push((a, b));
+            }
+            Constraint::NotNextTo(a, b) => {
+                let (a, b) = (get_guest(names, a)?, get_guest(names, b)?);
+                pairs.remove_edge(a, b);
+                pairs.remove_edge(b, a);
+            }
+        }
+    }
+
+    Ok((pairs, required))
+}
+
+fn are_neighbours(order: &[NodeId], a: NodeId, b: NodeId) -> bool {
+    (0..order.len()).any(|i| {
+        let (left, right) = (order[i], order[(i + 1) % order.len()]);
+        (left, right) == (a, b) || (left, right) == (b, a)
+    })
+}
+
+fn find_seating(seats: &[Seat], options: &SeatingOptions) -> Result<Seating, SeatingError> {
+    let (names, preferences) = make_preferences_graph(seats, &options.extra_guests)?;
+    let (pairs, required) =
+        apply_constraints(make_pairs_graph(&preferences), &names, &options.constraints)?;
+
+    let (_, order) =
+        best_hamiltonian_cycle(&pairs, |a, b| a > b).ok_or(SeatingError::Unsatisfiable)?;
+
+    if !required.iter().all(|&(a, b)| are_neighbours(&order, a, b)) {
+        return Err(SeatingError::Unsatisfiable);
+    }
+
+    let guest_happiness = |i: usize| {
+        let guest = order[i];
+        let left = order[(i + order.len() - 1) % order.len()];
+        let right = order[(i + 1) % order.len()];
+        preferences.weight(guest, left).unwrap_or(0) + preferences.weight(guest, right).unwrap_or(0)
+    };
+
+    let guests: Vec<_> = (0..order.len())
+        .map(|i| (names.name(order[i]).to_string(), guest_happiness(i)))
+        .collect();
+    let total = guests.iter().map(|(_, happiness)| happiness).sum();
 
-    max_happiness
+    Ok(Seating { guests, total })
 }
 
-pub fn solve_a(input: impl std::io::BufRead) -> Happiness {
-    solve(input, false)
+fn parse_seats(input: impl std::io::BufRead) -> Vec<Seat> {
+    input
+        .lines()
+        .map(|line| parse_seat(&line.unwrap()))
+        .collect()
 }
 
-pub fn solve_b(input: impl std::io::BufRead) -> Happiness {
-    solve(input, true)
+pub fn arrange_seating(
+    input: impl std::io::BufRead,
+    options: &SeatingOptions,
+) -> Result<Seating, SeatingError> {
+    find_seating(&parse_seats(input), options)
+}
+
+fn solve(input: impl std::io::BufRead, options: &SeatingOptions) -> Option<Happiness> {
+    match arrange_seating(input, options) {
+        Ok(seating) => Some(seating.total),
+        Err(SeatingError::Unsatisfiable) => None,
+        Err(e) => panic!("{}", e),
+    }
+}
+
+pub fn solve_a(input: impl std::io::BufRead) -> Option<Happiness> {
+    solve(input, &SeatingOptions::default())
+}
+
+pub fn solve_b(input: impl std::io::BufRead) -> Option<Happiness> {
+    let options = SeatingOptions {
+        extra_guests: vec![ExtraGuest::neutral(ME)],
+        ..Default::default()
+    };
+    solve(input, &options)
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
 
+    const TEST_INPUT: &str = "Alice would gain 54 happiness units by sitting next to Bob.\n\
+                              Alice would lose 79 happiness units by sitting next to Carol.\n\
+                              Alice would lose 2 happiness units by sitting next to David.\n\
+                              Bob would gain 83 happiness units by sitting next to Alice.\n\
+                              Bob would lose 7 happiness units by sitting next to Carol.\n\
+                              Bob would lose 63 happiness units by sitting next to David.\n\
+                              Carol would lose 62 happiness units by sitting next to Alice.\n\
+                              Carol would gain 60 happiness units by sitting next to Bob.\n\
+                              Carol would gain 55 happiness units by sitting next to David.\n\
+                              David would gain 46 happiness units by sitting next to Alice.\n\
+                              David would lose 7 happiness units by sitting next to Bob.\n\
+                              David would gain 41 happiness units by sitting next to Carol.";
+
     #[test]
     fn check_parsing() {
         assert_eq!(
@@ -140,25 +321,154 @@ mod test {
 
     #[test]
     fn check_a() {
+        assert_eq!(Some(330), solve_a(TEST_INPUT.as_bytes()));
+        assert_eq!(None, solve_a(&b""[..]));
+    }
+
+    #[test]
+    fn check_b() {
+        assert_eq!(Some(286), solve_b(TEST_INPUT.as_bytes()));
+    }
+
+    #[test]
+    fn check_seating() {
+        let seating = arrange_seating(TEST_INPUT.as_bytes(), &SeatingOptions::default()).unwrap();
+        assert_eq!(330, seating.total);
+        assert_eq!(
+            seating.total,
+            seating
+                .guests
+                .iter()
+                .map(|(_, happiness)| happiness)
+                .sum::<Happiness>()
+        );
+
+        let mut guests = seating.guests;
+        let start = guests.iter().position(|(name, _)| name == "Alice").unwrap();
+        guests.rotate_left(start);
+        if guests[1].0 != "Bob" {
+            guests[1..].reverse();
+        }
+
         assert_eq!(
-            330,
-            solve_a(
-                &b"Alice would gain 54 happiness units by sitting next to Bob.
-                   Alice would lose 79 happiness units by sitting next to Carol.
-                   Alice would lose 2 happiness units by sitting next to David.
-                   Bob would gain 83 happiness units by sitting next to Alice.
-                   Bob would lose 7 happiness units by sitting next to Carol.
-                   Bob would lose 63 happiness units by sitting next to David.
-                   Carol would lose 62 happiness units by sitting next to Alice.
-                   Carol would gain 60 happiness units by sitting next to Bob.
-                   Carol would gain 55 happiness units by sitting next to David.
-                   David would gain 46 happiness units by sitting next to Alice.
-                   David would lose 7 happiness units by sitting next to Bob.
-                   David would gain 41 happiness units by sitting next to Carol."[..]
-            )
-        )
+            vec![
+                (String::from("Alice"), 52),
+                (String::from("Bob"), 76),
+                (String::from("Carol"), 115),
+                (String::from("David"), 87),
+            ],
+            guests
+        );
+
+        let options = SeatingOptions {
+            extra_guests: vec![ExtraGuest::neutral(ME)],
+            ..Default::default()
+        };
+        let seating = arrange_seating(TEST_INPUT.as_bytes(), &options).unwrap();
+        assert_eq!(5, seating.guests.len());
+        assert!(seating
+            .guests
+            .iter()
+            .any(|(name, happiness)| name == ME && *happiness == 0));
+    }
+
+    fn are_seated_together(seating: &Seating, a: &str, b: &str) -> bool {
+        let names: Vec<&str> = seating
+            .guests
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect();
+        (0..names.len()).any(|i| {
+            let pair = (names[i], names[(i + 1) % names.len()]);
+            pair == (a, b) || pair == (b, a)
+        })
     }
 
     #[test]
-    fn check_b() {}
+    fn check_constraints() {
+        let arrange = |constraints: Vec<Constraint>| {
+            let options = SeatingOptions {
+                constraints,
+                ..Default::default()
+            };
+            arrange_seating(TEST_INPUT.as_bytes(), &options)
+        };
+        let next_to = |a: &str, b: &str| Constraint::NextTo(a.to_string(), b.to_string());
+        let not_next_to = |a: &str, b: &str| Constraint::NotNextTo(a.to_string(), b.to_string());
+
+        let seating = arrange(vec![next_to("Alice", "Carol")]).unwrap();
+        assert!(are_seated_together(&seating, "Alice", "Carol"));
+        assert_eq!(
+            seating.total,
+            seating
+                .guests
+                .iter()
+                .map(|(_, happiness)| happiness)
+                .sum::<Happiness>()
+        );
+        assert!(seating.total < 330);
+
+        let seating = arrange(vec![not_next_to("Alice", "Bob")]).unwrap();
+        assert!(!are_seated_together(&seating, "Alice", "Bob"));
+
+        assert_eq!(
+            Err(SeatingError::Unsatisfiable),
+            arrange(vec![next_to("Alice", "Bob"), not_next_to("Alice", "Bob")])
+        );
+        assert_eq!(
+            Err(SeatingError::Unsatisfiable),
+            arrange(vec![
+                next_to("Alice", "Bob"),
+                next_to("Alice", "Carol"),
+                next_to("Alice", "David")
+            ])
+        );
+        assert_eq!(
+            Err(SeatingError::UnknownGuest(String::from("Eve"))),
+            arrange(vec![next_to("Alice", "Eve")])
+        );
+
+        let options = SeatingOptions {
+            extra_guests: vec![ExtraGuest {
+                name: String::from("Eve"),
+                happiness: vec![(String::from("Bob"), 100)],
+                others_happiness: vec![(String::from("Carol"), -100)],
+            }],
+            constraints: vec![not_next_to("Eve", "Alice")],
+        };
+        let seating = arrange_seating(TEST_INPUT.as_bytes(), &options).unwrap();
+        assert_eq!(5, seating.guests.len());
+        assert!(are_seated_together(&seating, "Eve", "Bob"));
+        assert!(!are_seated_together(&seating, "Eve", "Alice"));
+    }
+
+    #[test]
+    fn check_many_guests() {
+        let names: Vec<String> = (b'a'..=b't')
+            .map(|c| (c as char).to_string().repeat(2))
+            .collect();
+
+        // every guest likes their alphabetical neighbours, so the best seating is alphabetical
+        let mut lines = Vec::new();
+        for (i, name) in names.iter().enumerate() {
+            for (j, neighbour) in names.iter().enumerate().filter(|&(j, _)| i != j) {
+                let distance = (i as isize - j as isize).abs();
+                let is_neighbour = distance == 1 || distance == names.len() as isize - 1;
+                let (verb, happiness) = if is_neighbour {
+                    ("gain", 10)
+                } else {
+                    ("lose", 1)
+                };
+                lines.push(format!(
+                    "{} would {} {} happiness units by sitting next to {}.",
+                    name, verb, happiness, neighbour
+                ));
+            }
+        }
+        let input = lines.join("\n");
+
+        let seating = arrange_seating(input.as_bytes(), &SeatingOptions::default()).unwrap();
+        assert_eq!(20 * names.len() as isize, seating.total);
+        assert!(seating.guests.iter().all(|&(_, happiness)| happiness == 20));
+    }
 }
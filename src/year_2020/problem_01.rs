@@ -37,22 +37,20 @@ fn find_three_entries_with_the_sum(entries: &[Entry], sum: Entry) -> Option<(Ent
     None
 }
 
-pub fn solve_a(input: impl std::io::BufRead) -> Entry {
+pub fn solve_a(input: impl std::io::BufRead) -> Option<Entry> {
     let entries = get_sorted_entries_vec(input);
 
-    let (first_entry, second_entry) =
-        find_two_entries_with_the_sum(&entries, 2020).expect("Solution not found");
+    let (first_entry, second_entry) = find_two_entries_with_the_sum(&entries, 2020)?;
 
-    first_entry * second_entry
+    Some(first_entry * second_entry)
 }
 
-pub fn solve_b(input: impl std::io::BufRead) -> Entry {
+pub fn solve_b(input: impl std::io::BufRead) -> Option<Entry> {
     let entries = get_sorted_entries_vec(input);
 
-    let (first_entry, second_entry, third_entry) =
-        find_three_entries_with_the_sum(&entries, 2020).expect("Solution not found");
+    let (first_entry, second_entry, third_entry) = find_three_entries_with_the_sum(&entries, 2020)?;
 
-    first_entry * second_entry * third_entry
+    Some(first_entry * second_entry * third_entry)
 }
 
 #[cfg(test)]
@@ -61,11 +59,13 @@ mod tests {
 
     #[test]
     fn check_a() {
-        assert_eq!(514579, super::solve_a(TEST_INPUT.as_bytes()));
+        assert_eq!(Some(514579), super::solve_a(TEST_INPUT.as_bytes()));
+        assert_eq!(None, super::solve_a("1721\n366".as_bytes()));
     }
 
     #[test]
     fn check_b() {
-        assert_eq!(241861950, super::solve_b(TEST_INPUT.as_bytes()));
+        assert_eq!(Some(241861950), super::solve_b(TEST_INPUT.as_bytes()));
+        assert_eq!(None, super::solve_b("1721\n299".as_bytes()));
     }
 }
@@ -7,17 +7,24 @@ fn get_input_path(problem: &str) -> std::path::PathBuf {
 }
 
 fn test_problem(problem: &str, input_file: &str, expected_result: &str) {
+    test_problem_with_args(&[problem], input_file, expected_result);
+}
+
+fn run_problem(args: &[&str], input_file: &str) -> std::process::Output {
     let app_path = std::path::PathBuf::from(env!("CARGO_BIN_EXE_rust_aoc"));
     let input_path = get_input_path(input_file);
 
     let input_file = std::fs::File::open(input_path).expect("failed to open the test input");
 
-    let output = std::process::Command::new(app_path)
-        .args(&[problem])
+    std::process::Command::new(app_path)
+        .args(args)
         .stdin(input_file)
         .output()
-        .expect("failed to run the test");
+        .expect("failed to run the test")
+}
 
+fn test_problem_with_args(args: &[&str], input_file: &str, expected_result: &str) {
+    let output = run_problem(args, input_file);
     let output_str =
         std::str::from_utf8(&output.stdout[..]).expect("failed to get the test output");
 
@@ -26,6 +33,19 @@ fn test_problem(problem: &str, input_file: &str, expected_result: &str) {
     assert_eq!(expected_result, output_str.trim());
 }
 
+fn test_problem_with_error(args: &[&str], input_file: &str, expected_error: &str) {
+    let output = run_problem(args, input_file);
+    let error_str = std::str::from_utf8(&output.stderr[..]).expect("failed to get the test error");
+
+    assert_eq!(Some(1), output.status.code());
+    assert!(output.stdout.is_empty());
+    assert_eq!(expected_error, error_str.trim());
+}
+
+fn test_problem_without_solution(args: &[&str], input_file: &str) {
+    test_problem_with_error(args, input_file, "No solution");
+}
+
 fn test_problems(input_file: &str, expected_result_1: &str, expected_result_2: &str) {
     let problem = input_file.replace("/", "_");
     let problem_a = format!("{}_a", problem);
@@ -40,11 +60,63 @@ fn test_2015_01() {
     test_problems("2015/01", "280", "1797");
 }
 
+#[test]
+fn test_2015_01_no_solution() {
+    test_problem_without_solution(&["2015_01_b"], "2015/01_no_basement");
+}
+
+#[test]
+fn test_2015_01_trace() {
+    test_problem_with_args(
+        &["2015_01_trace", "--strict", "--floor", "-1"],
+        "2015/01_trace",
+        "Final floor: 0\n\
+         Lowest floor: -1 (first reached at 5)\n\
+         Highest floor: 1 (first reached at 1)\n\
+         Time on floors:\n-1: 1\n0: 3\n1: 2\n\
+         Floor -1 first reached at 5",
+    );
+}
+
+#[test]
+fn test_2015_01_trace_invalid() {
+    test_problem_with_error(
+        &["2015_01_trace", "--strict"],
+        "2015/01_trace_invalid",
+        "Unexpected character 'x' at position 4",
+    );
+}
+
 #[test]
 fn test_2015_02() {
     test_problems("2015/02", "1586300", "3737498");
 }
 
+#[test]
+fn test_2015_02_report() {
+    test_problem_with_args(
+        &["2015_02_report", "--format", "csv"],
+        "2015/02_report",
+        "line,length,width,height,surface_area,slack,paper,ribbon,bow,total_ribbon\n\
+         1,2,3,4,52,6,58,10,24,34\n\
+         2,1,1,10,42,1,43,4,10,14",
+    );
+}
+
+#[test]
+fn test_2015_02_invalid() {
+    test_problem_with_error(
+        &["2015_02_a"],
+        "2015/02_invalid",
+        "Line 2: invalid dimensions '2x3'",
+    );
+    test_problem_with_error(
+        &["2015_02_report"],
+        "2015/02_invalid",
+        "Line 2: invalid dimensions '2x3'",
+    );
+}
+
 #[test]
 fn test_2015_03() {
     test_problems("2015/03", "2592", "2360");
@@ -60,41 +132,171 @@ fn test_2015_05() {
     test_problems("2015/05", "255", "55");
 }
 
+#[test]
+fn test_2015_05_rules() {
+    let rules_path = get_input_path("2015/05_rules");
+    let rules_path = rules_path.to_str().unwrap();
+    test_problem_with_args(&["2015_05", "--rules", rules_path], "2015/05", "55");
+}
+
 #[test]
 fn test_2015_06() {
     test_problems("2015/06", "569999", "17836115");
 }
 
+#[test]
+fn test_2015_06_rules() {
+    test_problem_with_args(&["2015_06", "--rules", "onoff"], "2015/06", "569999");
+    test_problem_with_args(&["2015_06", "--rules", "dimmer"], "2015/06", "17836115");
+    test_problem_with_args(
+        &["2015_06", "--rules", "saturating:4294967295"],
+        "2015/06",
+        "17836115",
+    );
+}
+
 #[test]
 fn test_2015_07() {
     test_problems("2015/07", "46065", "14134");
 }
 
+#[test]
+fn test_2015_07_no_solution() {
+    test_problem_without_solution(&["2015_07_a"], "2015/07_no_output");
+    test_problem_without_solution(&["2015_07_b"], "2015/07_no_output");
+}
+
+#[test]
+fn test_2015_07_simplify() {
+    test_problem_with_args(
+        &["2015_07_simplify", "--output", "a"],
+        "2015/07",
+        "46065 -> a",
+    );
+}
+
 #[test]
 fn test_2015_08() {
     test_problems("2015/08", "1333", "2046");
 }
 
+#[test]
+fn test_2015_08_dialects() {
+    test_problem_with_args(&["2015_08_a", "--dialect", "puzzle"], "2015/08", "1333");
+    test_problem_with_args(&["2015_08_b", "--dialect", "puzzle"], "2015/08", "2046");
+    test_problem_with_args(
+        &["2015_08_a", "--dialect", "rust", "--scan"],
+        "2015/08_rust",
+        "22",
+    );
+    test_problem_with_args(
+        &["2015_08_b", "--dialect", "rust", "--scan"],
+        "2015/08_rust",
+        "25",
+    );
+    test_problem_with_args(
+        &["2015_08_a", "--dialect", "rust", "--scan"],
+        "2015/08_rust_source",
+        "42",
+    );
+    test_problem_with_args(
+        &["2015_08_b", "--dialect", "rust", "--scan"],
+        "2015/08_rust_source",
+        "33",
+    );
+    test_problem_with_args(
+        &["2015_08_a", "--dialect", "python-raw", "--scan"],
+        "2015/08_python_raw",
+        "6",
+    );
+    test_problem_with_args(
+        &["2015_08_b", "--dialect", "python-raw", "--scan"],
+        "2015/08_python_raw",
+        "13",
+    );
+}
+
 #[test]
 fn test_2015_09() {
     test_problems("2015/09", "207", "804");
 }
 
+#[test]
+fn test_2015_09_explain() {
+    test_problem_with_args(
+        &["2015_09_a", "--explain"],
+        "2015/09",
+        "Tristram -> Tambi -> Snowdin -> AlphaCentauri -> Faerun -> Arbre -> Straylight -> Norrath
+Tristram -> Tambi = 49
+Tambi -> Snowdin = 15
+Snowdin -> AlphaCentauri = 12
+AlphaCentauri -> Faerun = 13
+Faerun -> Arbre = 24
+Arbre -> Straylight = 40
+Straylight -> Norrath = 54
+Total = 207",
+    );
+}
+
+#[test]
+fn test_2015_09_no_cities() {
+    test_problem_without_solution(&["2015_09_a"], "2015/09_empty");
+    test_problem_without_solution(&["2015_09_b"], "2015/09_empty");
+}
+
+#[test]
+fn test_2015_09_no_route() {
+    test_problem_without_solution(&["2015_09_a"], "2015/09_no_route");
+    test_problem_with_error(
+        &["2015_09_b", "--explain"],
+        "2015/09_no_route",
+        "Route not found, missing distances: London - Paris, London - Berlin, Dublin - Paris, Dublin - Berlin",
+    );
+}
+
 #[test]
 fn test_2015_10() {
     test_problems("2015/10", "252594", "3579328");
 }
 
+#[test]
+fn test_2015_10_conway() {
+    test_problem_with_args(&["2015_10", "--iterations", "50"], "2015/10", "3579328");
+    test_problem_with_args(
+        &["2015_10", "--iterations", "40", "--direct"],
+        "2015/10",
+        "252594",
+    );
+}
+
 #[test]
 fn test_2015_11() {
     test_problems("2015/11", "hxbxxyzz", "hxcaabcc");
 }
 
+#[test]
+fn test_2015_11_count() {
+    test_problem_with_args(
+        &["2015_11", "--count", "2"],
+        "2015/11",
+        "hxbxxyzz\nhxcaabcc",
+    );
+}
+
 #[test]
 fn test_2015_12() {
     test_problems("2015/12", "119433", "68466");
 }
 
+#[test]
+fn test_2015_12_rules() {
+    test_problem_with_args(
+        &["2015_12", "--rules", "object-value=red"],
+        "2015/12",
+        "68466",
+    );
+}
+
 #[test]
 fn test_2015_13() {
     test_problems("2015/13", "618", "601");